@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use lib_engine::math_utils::{vector3d::Vector3D, quaternion::Quaternion, matrix4x4::Matrix4x4};
+use lib_engine::transform::{Transform, Space};
+
+// General Gauss-Jordan inverse against the TRS shortcut Transform uses
+fn inverse(c: &mut Criterion) {
+    let position = Vector3D::new(-4.0, 8.0, 15.0);
+    let rotation = Quaternion::from_euler_angles(0.4, -1.2, 2.0);
+    let scale = Vector3D::new(0.5, 3.0, 2.0);
+    let matrix = Matrix4x4::from_trs(position, rotation, scale);
+
+    c.bench_function("general inverse", |b| {
+        b.iter(|| black_box(matrix).inverse().unwrap())
+    });
+
+    c.bench_function("trs inverse", |b| {
+        b.iter(|| Matrix4x4::inverse_trs(black_box(position), black_box(rotation), black_box(scale)))
+    });
+
+    // The translate only dirties the cache, it's done in setup so it isn't timed
+    c.bench_function("transform inverse after translate", |b| {
+        b.iter_batched(
+            || {
+                let mut transform = Transform::from_trs(position, rotation, scale);
+                transform.translate(Vector3D::new(0.0, 0.0, 0.0), Space::World);
+                transform
+            },
+            |transform| transform.inverse_matrix(),
+            BatchSize::SmallInput
+        )
+    });
+}
+
+criterion_group!(benches, inverse);
+criterion_main!(benches);
@@ -1,11 +1,13 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{self, DeriveInput};
+use syn::{self, DeriveInput, Data, Fields, Type};
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(reflect))]
 pub fn component_derive(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
-    let name = ast.ident;
+    let name = &ast.ident;
+    let reflect_impl = generate_reflect_impl(&ast);
     let generated_impl = quote! {
         impl Component for #name {
             fn as_any(&self) -> &dyn Any {
@@ -15,7 +17,17 @@ pub fn component_derive(input: TokenStream) -> TokenStream {
             fn as_any_mut(&mut self) -> &mut dyn Any {
                 self
             }
+
+            fn as_reflect(&self) -> Option<&dyn ::lib_engine::component::reflect::Reflect> {
+                Some(self)
+            }
+
+            fn as_reflect_mut(&mut self) -> Option<&mut dyn ::lib_engine::component::reflect::Reflect> {
+                Some(self)
+            }
         }
+
+        #reflect_impl
     };
 
     generated_impl.into()
@@ -33,3 +45,116 @@ pub fn component_type_derive(input: TokenStream) -> TokenStream {
 
     generated_impl.into()
 }
+
+// For plain structs that aren't components
+// Components already get this from the Component derive
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn reflect_derive(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    generate_reflect_impl(&ast).into()
+}
+
+fn generate_reflect_impl(ast: &DeriveInput) -> TokenStream2 {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields = match reflectable_fields(&ast.data) {
+        Ok(fields) => fields,
+        Err(error) => return error.to_compile_error()
+    };
+    let field_names = fields.iter().map(|(ident, _, _)| ident.to_string()).collect::<Vec<String>>();
+    let field_idents = fields.iter().map(|(ident, _, _)| *ident).collect::<Vec<&syn::Ident>>();
+    let field_types = fields.iter().map(|(_, ty, _)| *ty).collect::<Vec<&Type>>();
+    let type_names = fields.iter().map(|(_, _, type_name)| type_name).collect::<Vec<&String>>();
+
+    quote! {
+        impl #impl_generics ::lib_engine::component::reflect::Reflect for #name #ty_generics #where_clause {
+            fn fields(&self) -> &'static [::lib_engine::component::reflect::FieldInfo] {
+                use ::lib_engine::component::reflect::{FieldInfo, ReflectValue};
+                const FIELDS: &[FieldInfo] = &[
+                    #(FieldInfo {
+                        name: #field_names,
+                        type_name: #type_names,
+                        kind: <#field_types as ReflectValue>::KIND
+                    }),*
+                ];
+
+                FIELDS
+            }
+
+            fn get_field(&self, name: &str) -> Option<::lib_engine::component::reflect::FieldValue> {
+                use ::lib_engine::component::reflect::ReflectValue;
+                match name {
+                    #(#field_names => Some(self.#field_idents.to_field_value()),)*
+                    _ => None
+                }
+            }
+
+            fn set_field(&mut self, name: &str, value: ::lib_engine::component::reflect::FieldValue)
+                -> Result<(), ::lib_engine::component::reflect::ReflectError> {
+                use ::lib_engine::component::reflect::{ReflectError, ReflectValue};
+                match name {
+                    #(#field_names => {
+                        self.#field_idents = <#field_types as ReflectValue>::from_field_value(value, name)?;
+                        Ok(())
+                    })*
+                    _ => Err(ReflectError::UnknownField(name.to_string()))
+                }
+            }
+        }
+    }
+}
+
+// Every field with a plain type path is resolved through ReflectValue, so a type
+// only has to implement it to show up, and one named like an engine type
+// (say a user's own Color) is never mistaken for it
+// Fields with generics (Vec, HashMap...), references, tuples or arrays are left out,
+// anything else that isn't reflectable needs #[reflect(skip)]
+fn reflectable_fields(data: &Data) -> syn::Result<Vec<(&syn::Ident, &Type, String)>> {
+    let named_fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => &fields.named,
+            // Unit and tuple structs have no field names to show
+            _ => return Ok(vec![])
+        },
+        _ => return Ok(vec![])
+    };
+
+    let mut fields = vec![];
+    for field in named_fields {
+        if is_skipped(&field.attrs)? { continue }
+
+        let type_path = match &field.ty {
+            Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+            _ => continue
+        };
+        if type_path.segments.iter().any(|segment| !segment.arguments.is_empty()) { continue }
+
+        let type_name = type_path.segments.iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>()
+            .join("::");
+        if let Some(ident) = &field.ident {
+            fields.push((ident, &field.ty, type_name));
+        }
+    }
+
+    Ok(fields)
+}
+
+// Only #[reflect(skip)] exists for now, anything else is a compile error
+// rather than being quietly ignored
+fn is_skipped(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    let mut skipped = false;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("reflect")) {
+        let ident = attr.parse_args::<syn::Ident>().map_err(|_| {
+            syn::Error::new_spanned(attr, "Expected #[reflect(skip)]")
+        })?;
+        if ident != "skip" {
+            return Err(syn::Error::new_spanned(ident, "Unknown reflect option, expected `skip`"));
+        }
+        skipped = true;
+    }
+
+    Ok(skipped)
+}
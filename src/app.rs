@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// How many frames FrameStats averages over
+const FRAME_HISTORY: usize = 120;
+
+// Anything driven by GameLoop
+pub trait App {
+    // Runs at exactly the loop's fixed rate, delta_time is always the fixed step in seconds
+    fn fixed_update(&mut self, delta_time: f64);
+
+    // Runs once per frame after the fixed updates
+    // alpha (0..1) is how far past the last fixed update this frame is,
+    // drawing the previous and current states blended by it hides the uneven step count
+    fn render(&mut self, alpha: f64);
+}
+
+// Fixed-rate simulation with variable-rate rendering
+// The frame's time is handed to advance(), which runs as many fixed updates as fit
+// and carries the rest over to the next frame
+#[derive(Debug, Clone)]
+pub struct GameLoop {
+    fixed_step: Duration,
+    // More steps than this in one frame means the simulation can't keep up,
+    // so the time is dropped instead of falling further and further behind
+    max_steps_per_frame: u32,
+    frame_cap: Option<Duration>,
+    accumulator: Duration,
+    stats: FrameStats
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GameLoopError {
+    // Not above 0, or so small or large the step isn't a usable Duration
+    InvalidUpdateRate(f64)
+}
+
+impl fmt::Display for GameLoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUpdateRate(rate) => write!(f, "Invalid update rate {}", rate)
+        }
+    }
+}
+
+impl std::error::Error for GameLoopError {}
+
+impl GameLoop {
+    pub fn new(updates_per_second: f64) -> Result<Self, GameLoopError> {
+        // A zero step (infinite rate) is rejected too, advance() could never get through it
+        let fixed_step = Duration::try_from_secs_f64(1.0 / updates_per_second)
+            .ok()
+            .filter(|step| !step.is_zero())
+            .ok_or(GameLoopError::InvalidUpdateRate(updates_per_second))?;
+
+        Ok(Self {
+            fixed_step,
+            max_steps_per_frame: 8,
+            frame_cap: None,
+            accumulator: Duration::ZERO,
+            stats: FrameStats::new()
+        })
+    }
+
+    pub fn fixed_step(&self) -> Duration {
+        self.fixed_step
+    }
+
+    pub fn max_steps_per_frame(&self) -> u32 {
+        self.max_steps_per_frame
+    }
+
+    pub fn set_max_steps_per_frame(&mut self, max_steps: u32) {
+        self.max_steps_per_frame = max_steps.max(1);
+    }
+
+    pub fn fps_cap(&self) -> Option<f64> {
+        self.frame_cap.map(|cap| 1.0 / cap.as_secs_f64())
+    }
+
+    // None, or a cap with no usable frame time (not above 0, tiny, infinite),
+    // renders as fast as possible
+    pub fn set_fps_cap(&mut self, fps_cap: Option<f64>) {
+        self.frame_cap = fps_cap
+            .and_then(|fps| Duration::try_from_secs_f64(1.0 / fps).ok())
+            .filter(|cap| !cap.is_zero());
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    // When the next frame may start, given when the last one did
+    // None if uncapped or it's already time
+    pub fn wait_until(&self, last_frame: Instant, now: Instant) -> Option<Instant> {
+        let next_frame = last_frame + self.frame_cap?;
+        (next_frame > now).then_some(next_frame)
+    }
+
+    // Runs the fixed updates that fit in delta_time, then renders once
+    pub fn run_frame(&mut self, app: &mut impl App, delta_time: Duration) {
+        let timing = self.advance(delta_time);
+
+        for _ in 0..timing.steps {
+            app.fixed_update(self.fixed_step.as_secs_f64());
+        }
+        app.render(timing.alpha);
+    }
+
+    // Same as run_frame, for loops that don't fit the App trait
+    pub fn advance(&mut self, delta_time: Duration) -> FrameTiming {
+        self.stats.push(delta_time);
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_step {
+            if steps == self.max_steps_per_frame {
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+            self.accumulator -= self.fixed_step;
+            steps += 1;
+        }
+
+        FrameTiming {
+            steps,
+            alpha: self.accumulator.as_secs_f64() / self.fixed_step.as_secs_f64()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTiming {
+    // Fixed updates to run this frame, can be 0 when frames are faster than the fixed rate
+    pub steps: u32,
+    pub alpha: f64
+}
+
+// Frame times over the last FRAME_HISTORY frames
+// Everything is 0 until the first frame, never a division by 0
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    total: Duration
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.total -= self.frame_times.pop_front().unwrap();
+        }
+        self.frame_times.push_back(frame_time);
+        self.total += frame_time;
+    }
+
+    // Oldest first
+    pub fn frame_times(&self) -> impl Iterator<Item = &Duration> {
+        self.frame_times.iter()
+    }
+
+    pub fn last(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    pub fn average(&self) -> Duration {
+        match self.frame_times.len() {
+            0 => Duration::ZERO,
+            count => self.total / count as u32
+        }
+    }
+
+    pub fn min(&self) -> Duration {
+        self.frame_times.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.frame_times.iter().max().copied().unwrap_or_default()
+    }
+
+    // From the average, so a single slow frame doesn't make it jump around
+    pub fn fps(&self) -> f64 {
+        let average = self.average().as_secs_f64();
+        if average == 0.0 { 0.0 } else { 1.0 / average }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        updates: u32,
+        alphas: Vec<f64>
+    }
+
+    impl App for Counter {
+        fn fixed_update(&mut self, delta_time: f64) {
+            assert_eq!(delta_time, 0.01);
+            self.updates += 1;
+        }
+
+        fn render(&mut self, alpha: f64) {
+            self.alphas.push(alpha);
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn leftover_time_carries_over() {
+        let mut game_loop = GameLoop::new(100.0).unwrap();
+        let mut app = Counter::default();
+
+        game_loop.run_frame(&mut app, ms(25));
+        assert_eq!(app.updates, 2);
+
+        // 5ms left over plus 5ms makes another step
+        game_loop.run_frame(&mut app, ms(5));
+        assert_eq!(app.updates, 3);
+
+        game_loop.run_frame(&mut app, ms(4));
+        assert_eq!(app.updates, 3);
+        assert_eq!(app.alphas.len(), 3);
+        assert!((app.alphas[2] - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn steps_are_capped_per_frame() {
+        let mut game_loop = GameLoop::new(100.0).unwrap();
+        game_loop.set_max_steps_per_frame(4);
+
+        // A second long hitch shouldn't be caught up on all at once
+        let timing = game_loop.advance(ms(1000));
+        assert_eq!(timing, FrameTiming { steps: 4, alpha: 0.0 });
+
+        assert_eq!(game_loop.advance(ms(10)).steps, 1);
+    }
+
+    #[test]
+    fn invalid_update_rates() {
+        for rate in [0.0, -60.0, 1e-300, f64::INFINITY, f64::NAN] {
+            let error = GameLoop::new(rate).unwrap_err();
+            assert_eq!(error.to_string(), format!("Invalid update rate {}", rate));
+        }
+    }
+
+    #[test]
+    fn fps_cap_waits() {
+        let mut game_loop = GameLoop::new(60.0).unwrap();
+        let start = Instant::now();
+        assert_eq!(game_loop.wait_until(start, start), None);
+
+        game_loop.set_fps_cap(Some(50.0));
+        assert_eq!(game_loop.wait_until(start, start), Some(start + ms(20)));
+        assert_eq!(game_loop.wait_until(start, start + ms(20)), None);
+
+        game_loop.set_fps_cap(Some(0.0));
+        assert_eq!(game_loop.fps_cap(), None);
+
+        // A frame time too long for a Duration is no cap either
+        for fps in [1e-300, f64::INFINITY, f64::NAN] {
+            game_loop.set_fps_cap(Some(fps));
+            assert_eq!(game_loop.fps_cap(), None);
+        }
+    }
+
+    #[test]
+    fn frame_stats() {
+        let mut stats = FrameStats::new();
+        assert_eq!(stats.fps(), 0.0);
+        assert_eq!(stats.average(), Duration::ZERO);
+
+        // A zero length frame is no problem either
+        stats.push(Duration::ZERO);
+        assert_eq!(stats.fps(), 0.0);
+
+        stats.push(ms(20));
+        stats.push(ms(10));
+        assert_eq!(stats.average(), ms(10));
+        assert_eq!(stats.fps(), 100.0);
+        assert_eq!((stats.min(), stats.max(), stats.last()), (Duration::ZERO, ms(20), ms(10)));
+    }
+
+    #[test]
+    fn frame_stats_forget_old_frames() {
+        let mut stats = FrameStats::new();
+        stats.push(ms(1000));
+        for _ in 0..FRAME_HISTORY {
+            stats.push(ms(5));
+        }
+
+        assert_eq!(stats.max(), ms(5));
+        assert_eq!(stats.average(), ms(5));
+        assert_eq!(stats.frame_times().count(), FRAME_HISTORY);
+    }
+}
@@ -0,0 +1,496 @@
+use std::env;
+use std::io;
+use std::num::NonZeroU32;
+use std::f64::consts::PI;
+
+use winit::window::WindowBuilder;
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::event::{Event, WindowEvent, VirtualKeyCode, ElementState, MouseButton, ModifiersState};
+use winit::dpi::PhysicalSize;
+
+use softbuffer::{Context, Surface};
+
+use lib_engine::{scene, object, transform, math_utils, renderer, component, text};
+
+use scene::Scene;
+use object::{Camera, AspectRatio, ProjectionMode};
+use transform::Space;
+use component::mesh::Mesh;
+use component::reflect::FieldValue;
+use math_utils::vector3d::Vector3D;
+use renderer::{Renderer, Color};
+use text::{Font, TextStyle};
+
+const WIDTH: u32 = 960;
+const HEIGHT: u32 = 540;
+const DEFAULT_SCENE_PATH: &str = "./test_scene/scene.txt";
+
+const MOVE_STEP: f64 = 0.25;
+const ROTATE_STEP: f64 = PI / 36.0;
+const SCALE_STEP: f64 = 0.1;
+const MIN_SCALE: f64 = 0.05;
+
+// UI layout, in pixels
+const TOOLBAR_HEIGHT: isize = 20;
+const STATUS_BAR_HEIGHT: isize = 16;
+const PANEL_WIDTH: isize = 260;
+const TEXT_SCALE: usize = 2;
+const LINE_HEIGHT: isize = 14;
+
+const UI_BACKGROUND: (u8, u8, u8) = (30, 30, 36);
+const UI_BUTTON: (u8, u8, u8) = (60, 60, 70);
+const UI_BUTTON_ACTIVE: (u8, u8, u8) = (200, 120, 40);
+const UI_TEXT: (u8, u8, u8) = (230, 230, 230);
+const SELECTION_COLOR: (u8, u8, u8) = (255, 220, 0);
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tool {
+    Move,
+    Rotate,
+    Scale
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis {
+    X,
+    Y,
+    Z
+}
+
+impl Axis {
+    fn vector(&self, amount: f64) -> Vector3D {
+        match self {
+            Axis::X => Vector3D::new(amount, 0.0, 0.0),
+            Axis::Y => Vector3D::new(0.0, amount, 0.0),
+            Axis::Z => Vector3D::new(0.0, 0.0, amount)
+        }
+    }
+}
+
+struct Editor {
+    scene: Scene,
+    scene_path: String,
+    selected: Option<usize>,
+    tool: Tool,
+    axis: Axis,
+    cursor: (isize, isize),
+    // Set by mouse events, consumed by the UI or the viewport during the next frame
+    pending_click: Option<(isize, isize)>,
+    modifiers: ModifiersState,
+    unsaved_changes: bool,
+    status: String
+}
+
+impl Editor {
+    fn new(scene: Scene, scene_path: String, status: String) -> Self {
+        Self {
+            scene,
+            scene_path,
+            selected: None,
+            tool: Tool::Move,
+            axis: Axis::X,
+            cursor: (0, 0),
+            pending_click: None,
+            modifiers: ModifiersState::empty(),
+            unsaved_changes: false,
+            status
+        }
+    }
+
+    fn title(&self) -> String {
+        let marker = if self.unsaved_changes { "*" } else { "" };
+        format!("Zesty Editor - {}{}", self.scene_path, marker)
+    }
+
+    fn save(&mut self) {
+        self.status = match self.scene.save(&self.scene_path) {
+            Ok(()) => {
+                self.unsaved_changes = false;
+                format!("Saved to {}", self.scene_path)
+            },
+            Err(error) => format!("Save failed: {}", error)
+        };
+    }
+
+    fn select_next(&mut self) {
+        let count = self.scene.objects.len();
+        if count == 0 { return }
+
+        self.selected = Some(match self.selected {
+            Some(index) => (index + 1) % count,
+            None => 0
+        });
+    }
+
+    // Applies one step of the active tool on the selected object
+    fn nudge(&mut self, direction: f64) {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return
+        };
+        let transform = &mut self.scene.objects[index].transform;
+
+        match self.tool {
+            Tool::Move => transform.translate(self.axis.vector(MOVE_STEP * direction), Space::World),
+            Tool::Rotate => {
+                let angles = self.axis.vector(ROTATE_STEP * direction);
+                transform.rotate(angles.x, angles.y, angles.z, Space::World);
+            },
+            Tool::Scale => {
+                let scale = transform.scale() + self.axis.vector(SCALE_STEP * direction);
+                transform.set_scale(scale.max(Vector3D::new(MIN_SCALE, MIN_SCALE, MIN_SCALE)));
+            }
+        }
+
+        self.unsaved_changes = true;
+    }
+
+    fn toggle_projection(&mut self) {
+        let camera = &mut self.scene.camera;
+        let mode = match camera.projection_mode() {
+            ProjectionMode::Perspective => ProjectionMode::Orthographic,
+            ProjectionMode::Orthographic => ProjectionMode::Perspective
+        };
+
+        camera.set_projection_mode(mode);
+        self.unsaved_changes = true;
+        self.status = format!("{:?} view", mode);
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::W => self.tool = Tool::Move,
+            VirtualKeyCode::E => self.tool = Tool::Rotate,
+            VirtualKeyCode::R => self.tool = Tool::Scale,
+            VirtualKeyCode::X => self.axis = Axis::X,
+            VirtualKeyCode::Y => self.axis = Axis::Y,
+            VirtualKeyCode::Z => self.axis = Axis::Z,
+            VirtualKeyCode::Up | VirtualKeyCode::Right => self.nudge(1.0),
+            VirtualKeyCode::Down | VirtualKeyCode::Left => self.nudge(-1.0),
+            VirtualKeyCode::O => self.toggle_projection(),
+            VirtualKeyCode::Tab => self.select_next(),
+            VirtualKeyCode::Escape => self.selected = None,
+            VirtualKeyCode::S if self.modifiers.ctrl() => self.save(),
+            _ => {}
+        }
+    }
+
+    fn frame(&mut self, renderer: &mut Renderer) {
+        renderer.render(&mut self.scene);
+        self.draw_selection(renderer);
+        self.draw_ui(renderer);
+
+        // Clicks the UI didn't claim go to the viewport
+        if let Some((x, y)) = self.pending_click.take() {
+            let ray = self.scene.camera.pixel_to_ray(
+                x as f64, y as f64,
+                renderer.width(), renderer.height()
+            );
+            self.selected = self.scene.raycast(&ray).map(|hit| hit.object_index);
+        }
+    }
+
+    fn draw_selection(&mut self, renderer: &mut Renderer) {
+        let index = match self.selected {
+            Some(index) => index,
+            None => return
+        };
+
+        let selection_color = rgb(SELECTION_COLOR);
+        let object = &self.scene.objects[index];
+        let camera = &mut self.scene.camera;
+
+        if let Some(mesh) = object.get_component::<Mesh>() {
+            let pixels = mesh.vertices().iter()
+                .map(|vertex| renderer.project_to_pixel(camera, object.transform.to_world_space(*vertex)))
+                .collect::<Vec<Option<(isize, isize)>>>();
+
+            for triangle in mesh.triangles() {
+                for i in 0..3 {
+                    let start = pixels[triangle[i]];
+                    let end = pixels[triangle[(i + 1) % 3]];
+                    if let (Some(start), Some(end)) = (start, end) {
+                        renderer.bresenham_line(selection_color, start.0, start.1, end.0, end.1);
+                    }
+                }
+            }
+        }
+
+        // Axis gizmo, with the active axis drawn thicker
+        let origin = object.transform.position();
+        let origin_pixel = match renderer.project_to_pixel(camera, origin) {
+            Some(pixel) => pixel,
+            None => return
+        };
+
+        let gizmo_axes = [
+            (Axis::X, Color::new(255, 60, 60)),
+            (Axis::Y, Color::new(60, 255, 60)),
+            (Axis::Z, Color::new(60, 60, 255))
+        ];
+
+        for (axis, color) in gizmo_axes {
+            let tip = match renderer.project_to_pixel(camera, origin + axis.vector(1.0)) {
+                Some(pixel) => pixel,
+                None => continue
+            };
+
+            let thickness = if axis == self.axis { 2 } else { 0 };
+            for offset in 0..=thickness {
+                renderer.bresenham_line(color,
+                    origin_pixel.0 + offset, origin_pixel.1,
+                    tip.0 + offset, tip.1);
+            }
+        }
+    }
+
+    fn draw_ui(&mut self, renderer: &mut Renderer) {
+        let width = renderer.width() as isize;
+        let height = renderer.height() as isize;
+
+        // Toolbar
+        renderer.fill_rect(0, 0, width, TOOLBAR_HEIGHT, rgb(UI_BACKGROUND));
+        let mut ui = Ui { x: 4, click: self.pending_click.take() };
+
+        for (tool, label) in [(Tool::Move, "Move"), (Tool::Rotate, "Rotate"), (Tool::Scale, "Scale")] {
+            if ui.button(renderer, label, self.tool == tool) {
+                self.tool = tool;
+            }
+        }
+
+        ui.x += 12;
+        for (axis, label) in [(Axis::X, "X"), (Axis::Y, "Y"), (Axis::Z, "Z")] {
+            if ui.button(renderer, label, self.axis == axis) {
+                self.axis = axis;
+            }
+        }
+
+        ui.x += 12;
+        for (direction, label) in [(-1.0, "-"), (1.0, "+")] {
+            if ui.button(renderer, label, false) {
+                self.nudge(direction);
+            }
+        }
+
+        ui.x += 12;
+        if ui.button(renderer, "Save", false) {
+            self.save();
+        }
+
+        self.pending_click = ui.click;
+
+        // Clicks on the rest of the toolbar shouldn't fall through to the viewport
+        if matches!(self.pending_click, Some((_, y)) if y < TOOLBAR_HEIGHT) {
+            self.pending_click = None;
+        }
+
+        // Properties panel
+        if let Some(index) = self.selected {
+            let panel_x = width - PANEL_WIDTH;
+            let panel_height = height - TOOLBAR_HEIGHT - STATUS_BAR_HEIGHT;
+            renderer.fill_rect(panel_x, TOOLBAR_HEIGHT, PANEL_WIDTH, panel_height, rgb(UI_BACKGROUND));
+
+            if matches!(self.pending_click, Some((x, _)) if x >= panel_x) {
+                self.pending_click = None;
+            }
+
+            let lines = self.property_lines(index);
+            for (i, line) in lines.iter().enumerate() {
+                let y = TOOLBAR_HEIGHT + 6 + (i as isize * LINE_HEIGHT);
+                draw_text(renderer, panel_x + 6, y, line, rgb(UI_TEXT));
+            }
+        }
+
+        // Status bar
+        let status_y = height - STATUS_BAR_HEIGHT;
+        renderer.fill_rect(0, status_y, width, STATUS_BAR_HEIGHT, rgb(UI_BACKGROUND));
+        let help = "W/E/R tool  X/Y/Z axis  arrows apply  tab next  O ortho  ctrl+s save";
+        draw_text(renderer, 4, status_y + 3, &format!("{}  |  {}", help, self.status), rgb(UI_TEXT));
+    }
+
+    fn property_lines(&self, index: usize) -> Vec<String> {
+        let object = &self.scene.objects[index];
+        let position = object.transform.position();
+        let [w, x, y, z] = object.transform.rotation().components();
+        let scale = object.transform.scale();
+
+        let mut lines = vec![
+            format!("Object {}", index),
+            format!("Pos {:.2} {:.2} {:.2}", position.x, position.y, position.z),
+            format!("Rot {:.2} {:.2} {:.2} {:.2}", w, x, y, z),
+            format!("Scale {:.2} {:.2} {:.2}", scale.x, scale.y, scale.z),
+        ];
+
+        for (name, component) in object.components() {
+            lines.push(String::new());
+            lines.push(name.to_string());
+
+            let reflect = match component.as_reflect() {
+                Some(reflect) => reflect,
+                None => continue
+            };
+
+            for field in reflect.fields() {
+                let value = reflect.get_field(field.name)
+                    .map(|value| format_field_value(&value))
+                    .unwrap_or_default();
+                lines.push(format!(" {}: {}", field.name, value));
+            }
+        }
+
+        lines
+    }
+}
+
+// Bare-bones immediate mode UI: buttons are laid out left to right,
+// drawn and hit-tested against the frame's click in the same call
+struct Ui {
+    x: isize,
+    click: Option<(isize, isize)>
+}
+
+impl Ui {
+    fn button(&mut self, renderer: &mut Renderer, label: &str, active: bool) -> bool {
+        let width = text_width(label) + 8;
+        let (x, y, height) = (self.x, 2, TOOLBAR_HEIGHT - 4);
+        self.x += width + 4;
+
+        let color = if active { UI_BUTTON_ACTIVE } else { UI_BUTTON };
+        renderer.fill_rect(x, y, width, height, rgb(color));
+        draw_text(renderer, x + 4, y + 3, label, rgb(UI_TEXT));
+
+        match self.click {
+            Some((click_x, click_y))
+            if click_x >= x && click_x < x + width && click_y >= y && click_y < y + height => {
+                self.click = None;
+                true
+            },
+            _ => false
+        }
+    }
+}
+
+fn rgb(color: (u8, u8, u8)) -> Color {
+    Color::new(color.0, color.1, color.2)
+}
+
+fn format_field_value(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Integer(num) => num.to_string(),
+        FieldValue::Float(num) => format!("{:.3}", num),
+        FieldValue::Bool(flag) => flag.to_string(),
+        FieldValue::String(text) => text.clone(),
+        FieldValue::Vector3D(v) => format!("{:.2} {:.2} {:.2}", v.x, v.y, v.z),
+        FieldValue::Color(color) => color.to_hex()
+    }
+}
+
+fn text_width(text: &str) -> isize {
+    (Font::builtin().text_width(text) * TEXT_SCALE) as isize
+}
+
+fn draw_text(renderer: &mut Renderer, x: isize, y: isize, text: &str, color: Color) {
+    text::draw_text(renderer, Font::builtin(), x, y, text, &TextStyle::new(color).scale(TEXT_SCALE));
+}
+
+fn resize_framebuffer(surface: &mut Surface, renderer: &mut Renderer, size: PhysicalSize<u32>) {
+    match (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+        (Some(width), Some(height)) => {
+            surface.resize(width, height).unwrap();
+            renderer.resize(size.width as usize, size.height as usize);
+        },
+        _ => renderer.resize(0, 0)
+    }
+}
+
+fn load_or_create_scene(scene_path: &str) -> io::Result<(Scene, String)> {
+    match Scene::load(scene_path) {
+        Ok(scene) => Ok((scene, format!("Loaded {}", scene_path))),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            let scene = Scene {
+                objects: vec![],
+                camera: Camera::new(1, 30, 90, AspectRatio(16.0, 9.0))
+            };
+            Ok((scene, "New scene".to_string()))
+        },
+        Err(error) => Err(error)
+    }
+}
+
+pub fn main() {
+    let scene_path = env::args().nth(1).unwrap_or(DEFAULT_SCENE_PATH.to_string());
+    let (scene, status) = match load_or_create_scene(&scene_path) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("Failed to load {}: {}", scene_path, error);
+            return;
+        }
+    };
+
+    let mut editor = Editor::new(scene, scene_path, status);
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title(editor.title())
+        .with_inner_size(PhysicalSize::new(WIDTH, HEIGHT))
+        .build(&event_loop).unwrap();
+
+    let (width, height) = { let size = window.inner_size(); (size.width, size.height) };
+
+    let context = unsafe { Context::new(&window) }.unwrap();
+    let mut surface = unsafe { Surface::new(&context, &window) }.unwrap();
+
+    let mut renderer = Renderer::new(width as usize, height as usize);
+    surface.resize(
+        NonZeroU32::new(width).unwrap(),
+        NonZeroU32::new(height).unwrap()
+    ).unwrap();
+
+    event_loop.run(move |event, _, control_flow| {
+        match event {
+            Event::WindowEvent { window_id, event } if window_id == window.id() => match event {
+                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+
+                WindowEvent::Resized(size) => resize_framebuffer(&mut surface, &mut renderer, size),
+
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    resize_framebuffer(&mut surface, &mut renderer, *new_inner_size);
+                },
+
+                WindowEvent::ModifiersChanged(modifiers) => editor.modifiers = modifiers,
+
+                WindowEvent::CursorMoved { position, .. } => {
+                    editor.cursor = (position.x as isize, position.y as isize);
+                },
+
+                WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                    editor.pending_click = Some(editor.cursor);
+                },
+
+                WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+                    if let Some(key) = input.virtual_keycode {
+                        editor.handle_key(key);
+                    }
+                },
+
+                _ => {}
+            },
+
+            Event::MainEventsCleared if renderer.width() == 0 || renderer.height() == 0 => {}
+
+            Event::MainEventsCleared => {
+                editor.frame(&mut renderer);
+                window.set_title(&editor.title());
+
+                let mut buffer = surface.buffer_mut().unwrap();
+                buffer.copy_from_slice(renderer.buffer());
+                buffer.present().unwrap();
+                renderer.clear_tmp_buffer();
+                editor.scene.end_frame();
+            }
+
+            _ => {}
+        }
+    });
+}
@@ -1,43 +1,288 @@
+use std::io;
+use std::env;
 use std::num::NonZeroU32;
-use std::time::Instant;
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
 
 use winit::window::WindowBuilder;
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::event::{Event, WindowEvent, VirtualKeyCode, ElementState};
+use winit::event::{Event, WindowEvent};
 use winit::dpi::PhysicalSize;
 
 use softbuffer::{Context, Surface};
 
-use lib_engine::{scene, object, math_utils, renderer, component};
+use lib_engine::{app, scene, object, transform, math_utils, renderer, component, controller, input, recording, overlay, debug_draw};
 
 use scene::Scene;
 use object::{Object, Camera, AspectRatio};
+use transform::{Transform, Space};
 use component::mesh::Mesh;
 use math_utils::vector3d::Vector3D;
-use renderer::Renderer;
+use renderer::{Renderer, Color, HdrSettings, ToneMapping, PostEffect};
+use controller::{OrbitController, FlyController};
+use input::{Input, InputMap};
+use recording::Recording;
+use app::{App, GameLoop, FrameStats};
+use overlay::DebugOverlay;
+use debug_draw::DebugStyle;
+
+// Used when ./test_scene/input.txt doesn't exist, which can override any of these
+const DEFAULT_BINDINGS: &str = "
+action print_fps Space
+action switch_camera Tab
+action toggle_overlay F3
+action toggle_gizmos F4
+action drag MouseLeft
+action pan MouseRight
+
+axis move_x D A
+axis move_y E Q
+axis move_z W S
+axis look_x MouseX
+axis look_y MouseY
+axis zoom Wheel
+";
+
+const USAGE: &str = "Usage: runner [--record <file>] [--replay <file> [--headless]] [--fps-cap <fps>] [--hdr <clamp|reinhard|aces>] [--exposure <stops>] [--post <file>]";
+
+const UPDATES_PER_SECOND: f64 = 60.0;
+
+enum CameraMode {
+    Orbit(OrbitController),
+    Fly(FlyController)
+}
+
+// From the command line, --hdr or --exposure turn HDR on
+#[derive(Default)]
+struct RenderOptions {
+    hdr: Option<HdrSettings>,
+    post_effects: Vec<PostEffect>
+}
+
+struct Game {
+    scene: Scene,
+    renderer: Renderer,
+    input: Input,
+    camera_mode: CameraMode,
+    orbit_target: Vector3D,
+    // Camera first, then the objects, as they were before the last fixed update
+    previous_transforms: Vec<Transform>,
+    overlay: DebugOverlay,
+    // Axes on every object and a sphere on the orbit target
+    show_gizmos: bool,
+    // Only for display, it must not affect the simulation
+    frame_stats: FrameStats,
+    // Time spent in fixed updates since the last render
+    update_time: Duration
+}
+
+impl Game {
+    fn new(width: usize, height: usize, options: RenderOptions) -> Self {
+        // Boilerplate section for testing
+        let mut cube = Object::new();
+        cube.transform.set_position(Vector3D::new(0, 0, 5));
+
+        let mut mesh = Mesh::new();
+        mesh.load_obj("./test_scene/tinker.obj").unwrap();
+        cube.add_component(mesh);
+
+        let camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let orbit_target = cube.transform.position();
+
+        let scene = Scene {
+            objects: vec![cube],
+            camera
+        };
+
+        // End boilerplate section
+
+        let input_map = match InputMap::load("./test_scene/input.txt") {
+            Ok(map) => map,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => InputMap::parse(DEFAULT_BINDINGS).unwrap(),
+            Err(error) => panic!("Failed to load input.txt: {}", error)
+        };
+
+        // Orbit: left drag to rotate, right drag to pan, wheel to zoom
+        // Fly: left drag to look, WASD to move, Q/E to go down/up, wheel for speed
+        // Tab switches between the two
+        let mut renderer = Renderer::new(width, height);
+        if let Some(settings) = options.hdr {
+            renderer.set_hdr(true);
+            *renderer.hdr_settings_mut() = settings;
+        }
+        *renderer.post_effects_mut() = options.post_effects;
+
+        Self {
+            camera_mode: CameraMode::Orbit(OrbitController::from_transform(&scene.camera.transform, orbit_target)),
+            input: Input::new(input_map),
+            previous_transforms: vec![],
+            scene,
+            renderer,
+            orbit_target,
+            overlay: DebugOverlay::new(),
+            show_gizmos: false,
+            frame_stats: FrameStats::new(),
+            update_time: Duration::ZERO
+        }
+    }
+
+    fn transforms_mut(&mut self) -> impl Iterator<Item = &mut Transform> {
+        std::iter::once(&mut self.scene.camera.transform)
+            .chain(self.scene.objects.iter_mut().map(|object| &mut object.transform))
+    }
+}
+
+impl App for Game {
+    // Everything that depends on input or time
+    // Must only depend on its arguments and the input, or replays stop matching
+    fn fixed_update(&mut self, delta_time: f64) {
+        let update_start = Instant::now();
+        self.previous_transforms = self.transforms_mut().map(|transform| transform.clone()).collect();
+
+        let input = &self.input;
+
+        if input.action_pressed("print_fps") { println!("FPS: {:.1}", self.frame_stats.fps()) }
+        if input.action_pressed("toggle_overlay") { self.overlay.toggle() }
+        if input.action_pressed("toggle_gizmos") { self.show_gizmos = !self.show_gizmos }
+        if input.action_pressed("switch_camera") {
+            let transform = &self.scene.camera.transform;
+            self.camera_mode = match self.camera_mode {
+                CameraMode::Orbit(_) => CameraMode::Fly(FlyController::from_transform(transform)),
+                CameraMode::Fly(_) => CameraMode::Orbit(OrbitController::from_transform(transform, self.orbit_target))
+            };
+        }
+
+        let camera = &mut self.scene.camera.transform;
+        let (look_x, look_y) = (input.axis("look_x"), input.axis("look_y"));
+
+        match &mut self.camera_mode {
+            CameraMode::Orbit(orbit) => {
+                if input.action_held("drag") { orbit.rotate(look_x, look_y) }
+                if input.action_held("pan") { orbit.pan(look_x, look_y) }
+                orbit.zoom(input.axis("zoom"));
+                orbit.apply(camera);
+            },
+            CameraMode::Fly(fly) => {
+                if input.action_held("drag") { fly.look(look_x, look_y) }
+                fly.move_speed *= 1.1_f64.powf(input.axis("zoom"));
+
+                let movement = Vector3D::new(input.axis("move_x"), input.axis("move_y"), input.axis("move_z"));
+                fly.update(camera, movement, delta_time);
+            }
+        }
+        // Input only counts for the first update of the frame, later ones only see what's held
+        self.input.end_frame();
+
+        let rot = (PI/4.0) * (1.0/200.0);
+        for obj in self.scene.objects.iter_mut() {
+            obj.transform.rotate(rot, 0.0, rot, Space::World);
+        }
+
+        self.update_time += update_start.elapsed();
+    }
+
+    // Draws between the previous and current fixed update, then puts the current state back
+    fn render(&mut self, alpha: f64) {
+        let previous_transforms = std::mem::take(&mut self.previous_transforms);
+        let current_transforms = self.transforms_mut()
+            .zip(previous_transforms.iter())
+            .map(|(transform, previous)| {
+                let interpolated = previous.interpolate(transform, alpha);
+                std::mem::replace(transform, interpolated)
+            })
+            .collect::<Vec<Transform>>();
+
+        self.renderer.clear_tmp_buffer();
+        self.renderer.render(&mut self.scene);
+        self.renderer.resolve();
+        self.renderer.post_process();
+
+        if self.show_gizmos {
+            for object in self.scene.objects.iter() {
+                debug_draw::axes(&object.transform, 1.5);
+            }
+            debug_draw::wire_sphere(self.orbit_target, 0.2, DebugStyle::new(Color::new(255, 255, 255)));
+        }
+        debug_draw::render(&mut self.renderer, &mut self.scene.camera);
+
+        for (transform, current) in self.transforms_mut().zip(current_transforms) {
+            *transform = current;
+        }
+        self.previous_transforms = previous_transforms;
+        self.scene.end_frame();
+
+        self.overlay.draw(&mut self.renderer, &self.frame_stats, &[("Update", self.update_time)]);
+        self.update_time = Duration::ZERO;
+    }
+}
 
 pub fn main() {
-    // Boilerplate section for testing
-    let mut cube = Object::new();
-    cube.transform.translate(Vector3D::new(0, 0, 5));
+    let mut record_path: Option<String> = None;
+    let mut replay: Option<Recording> = None;
+    let mut headless = false;
+    let mut fps_cap: Option<f64> = None;
+    let mut options = RenderOptions::default();
 
-    let mut mesh = Mesh::new();
-    mesh.load_obj("./test_scene/tinker.obj").unwrap();
-    cube.add_component(mesh);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record_path = Some(args.next().expect(USAGE)),
+            "--replay" => {
+                let path = args.next().expect(USAGE);
+                replay = Some(Recording::load(&path).unwrap_or_else(|error| panic!("Failed to load {}: {}", path, error)));
+            },
+            "--headless" => headless = true,
+            "--fps-cap" => fps_cap = Some(args.next().and_then(|fps| fps.parse().ok()).expect(USAGE)),
+            "--hdr" => options.hdr.get_or_insert_with(HdrSettings::default).tone_mapping =
+                args.next().and_then(|name| ToneMapping::from_name(&name)).expect(USAGE),
+            "--exposure" => options.hdr.get_or_insert_with(HdrSettings::default).exposure =
+                args.next().and_then(|stops| stops.parse().ok()).expect(USAGE),
+            "--post" => {
+                let path = args.next().expect(USAGE);
+                options.post_effects = renderer::post::load(&path).unwrap_or_else(|error| panic!("Failed to load {}: {}", path, error));
+            },
+            _ => panic!("{}", USAGE)
+        }
+    }
+
+    let mut game_loop = GameLoop::new(UPDATES_PER_SECOND).unwrap();
+    game_loop.set_fps_cap(fps_cap);
+
+    match (replay, headless) {
+        (Some(replay), true) => run_headless(game_loop, replay, options),
+        (None, true) => panic!("{}", USAGE),
+        (replay, false) => run_window(game_loop, replay, record_path, options)
+    }
+}
 
-    let camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+// Plays a recording back without opening a window, eg. on CI,
+// and prints a checksum of the last frame to compare runs with
+fn run_headless(mut game_loop: GameLoop, replay: Recording, options: RenderOptions) {
+    let mut game = Game::new(replay.width, replay.height, options);
 
-    let mut scene = Scene {
-        objects: vec![cube],
-        camera
-    };
+    for frame in replay.frames.iter() {
+        for &event in frame.events.iter() {
+            game.input.apply(event);
+        }
+        // The overlay's stage timings still vary, so a recording that shows it won't checksum the same
+        game.frame_stats = game_loop.stats().clone();
+        game_loop.run_frame(&mut game, Duration::from_micros(frame.delta_time as u64));
+    }
+
+    println!("Replayed {} frames, last frame checksum: {:016x}", replay.frames.len(), checksum(game.renderer.buffer()));
+}
 
-    // End boilerplate section
-    
+// FNV-1a, which unlike std's hasher is guaranteed to stay the same between Rust versions
+fn checksum(buffer: &[u32]) -> u64 {
+    buffer.iter()
+        .flat_map(|pixel| pixel.to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn run_window(mut game_loop: GameLoop, replay: Option<Recording>, record_path: Option<String>, options: RenderOptions) {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_inner_size(PhysicalSize::new(640, 360))
-        .with_resizable(false)
         .build(&event_loop).unwrap();
 
     // Renderer init
@@ -46,41 +291,98 @@ pub fn main() {
     let context = unsafe { Context::new(&window) }.unwrap();
     let mut surface = unsafe { Surface::new(&context, &window) }.unwrap();
 
-    let mut renderer = Renderer::new(width as usize, height as usize);
+    let mut game = Game::new(width as usize, height as usize, options);
     surface.resize(
         NonZeroU32::new(width).unwrap(),
         NonZeroU32::new(height).unwrap()
     ).unwrap();
 
+    let mut recording = record_path.as_ref().map(|_| Recording::new(width as usize, height as usize));
+    // While replaying, live input is ignored and the recorded delta times replace the wall clock
+    let mut replay_frames = replay.map(|replay| replay.frames.into_iter());
 
-    let mut now = Instant::now();
-    let mut delta_time = 0;
+    let mut last_frame = Instant::now();
     event_loop.run(move |event, _, control_flow| {
         match event {
             Event::WindowEvent { window_id, event: WindowEvent::CloseRequested }
                 if window_id == window.id() => { *control_flow = ControlFlow::Exit }
 
-            Event::WindowEvent { window_id, event: WindowEvent::KeyboardInput { input, ..} }
+            Event::WindowEvent { window_id, event: WindowEvent::Resized(size) }
+                if window_id == window.id() => {
+                    resize_framebuffer(&mut surface, &mut game.renderer, size);
+                }
+
+            Event::WindowEvent { window_id, event: WindowEvent::ScaleFactorChanged { new_inner_size, .. } }
                 if window_id == window.id() => {
-                    if input.virtual_keycode == Some(VirtualKeyCode::Space) && input.state == ElementState::Pressed {
-                        println!("FPS: {}", 1_000_000 / delta_time);
+                    resize_framebuffer(&mut surface, &mut game.renderer, *new_inner_size);
+                }
+
+            Event::WindowEvent { window_id, ref event }
+                if window_id == window.id() && replay_frames.is_none() => {
+                    if let Some(input_event) = game.input.handle_event(event) {
+                        if let Some(recording) = recording.as_mut() { recording.record(input_event) }
                     }
                 }
 
+            // Nothing to draw into while minimized
+            Event::MainEventsCleared if game.renderer.width() == 0 || game.renderer.height() == 0 => {}
+
             Event::MainEventsCleared => {
-                let mut buffer = surface.buffer_mut().unwrap();
-                renderer.render(&mut scene);
+                let now = Instant::now();
+                if let Some(next_frame) = game_loop.wait_until(last_frame, now) {
+                    *control_flow = ControlFlow::WaitUntil(next_frame);
+                    return;
+                }
+                *control_flow = ControlFlow::Poll;
+
+                // Whole microseconds, so a recording replays the exact same steps
+                let delta_time = match replay_frames.as_mut() {
+                    Some(frames) => match frames.next() {
+                        Some(frame) => {
+                            frame.events.into_iter().for_each(|event| game.input.apply(event));
+                            frame.delta_time
+                        },
+                        None => {
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                    },
+                    None => now.duration_since(last_frame).as_micros()
+                };
+                last_frame = now;
 
-                buffer.copy_from_slice(renderer.buffer()) ;
+                if let Some(recording) = recording.as_mut() { recording.end_frame(delta_time) }
+                game.frame_stats = game_loop.stats().clone();
+                game_loop.run_frame(&mut game, Duration::from_micros(delta_time as u64));
 
+                let mut buffer = surface.buffer_mut().unwrap();
+                buffer.copy_from_slice(game.renderer.buffer());
                 buffer.present().unwrap();
-                renderer.clear_tmp_buffer();
+            }
 
-                delta_time = now.elapsed().as_micros();
-                now = Instant::now();
+            Event::LoopDestroyed => {
+                if let (Some(recording), Some(path)) = (recording.as_ref(), record_path.as_ref()) {
+                    match recording.save(path) {
+                        Ok(()) => println!("Saved {} frames to {}", recording.frames.len(), path),
+                        Err(error) => eprintln!("Failed to save the recording: {}", error)
+                    }
+                }
             }
 
             _ => {}
         }
     });
 }
+
+// Softbuffer and the renderer must always agree on the size,
+// otherwise copying the frame over panics
+fn resize_framebuffer(surface: &mut Surface, renderer: &mut Renderer, size: PhysicalSize<u32>) {
+    match (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+        (Some(width), Some(height)) => {
+            surface.resize(width, height).unwrap();
+            renderer.resize(size.width as usize, size.height as usize);
+        },
+        // Minimized, keep the surface as is but stop rendering
+        _ => renderer.resize(0, 0)
+    }
+}
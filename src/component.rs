@@ -4,11 +4,25 @@ use std::any::Any;
 use lib_derive::{Component, ComponentType};
 
 pub mod mesh;
+pub mod material;
+pub mod reflect;
+
+use reflect::Reflect;
 
 
 pub trait Component {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    // Derived components expose their fields for the editor
+    // Manual impls can opt out by keeping these defaults
+    fn as_reflect(&self) -> Option<&dyn Reflect> {
+        None
+    }
+
+    fn as_reflect_mut(&mut self) -> Option<&mut dyn Reflect> {
+        None
+    }
 }
 
 impl fmt::Debug for dyn Component {
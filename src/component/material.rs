@@ -0,0 +1,57 @@
+use std::any::Any;
+
+use crate::renderer::{Color, BlendMode};
+use super::{Component, ComponentType};
+
+
+// How a mesh is colored, objects without one get the debug palette
+// Anything not Opaque is drawn in the transparent pass, after every opaque object
+#[derive(Debug, Clone, Copy, PartialEq, Component, ComponentType)]
+pub struct Material {
+    // Its alpha only matters when blending
+    pub color: Color,
+    // Multiplies the color in linear light, past 1 it's brighter than white,
+    // which only shows with HDR on
+    pub intensity: f64,
+    #[reflect(skip)]
+    pub blend_mode: BlendMode
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new(Color::new(255, 255, 255))
+    }
+}
+
+impl Material {
+    pub fn new(color: Color) -> Self {
+        Self { color, intensity: 1.0, blend_mode: BlendMode::Opaque }
+    }
+
+    pub fn transparent(color: Color, blend_mode: BlendMode) -> Self {
+        Self { color, intensity: 1.0, blend_mode }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.blend_mode != BlendMode::Opaque
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transparency() {
+        assert!(!Material::default().is_transparent());
+
+        let glass = Material::transparent(Color::rgba(200, 220, 255, 80), BlendMode::Alpha);
+        assert!(glass.is_transparent());
+        assert_eq!(glass.color.alpha(), 80);
+
+        // The blend mode isn't exposed to the editor
+        let fields = glass.as_reflect().unwrap().fields();
+        assert_eq!(fields.iter().map(|field| field.name).collect::<Vec<&str>>(), ["color", "intensity"]);
+    }
+}
@@ -3,6 +3,7 @@ use std::io::{self, BufRead, BufReader};
 use std::any::Any;
 
 use crate::math_utils::vector3d::Vector3D;
+use crate::math_utils::ray::{Ray, TriangleHit};
 use super::{Component, ComponentType};
 
 
@@ -13,6 +14,12 @@ pub struct Mesh {
     triangles: Vec<[usize; 3]>
 }
 
+impl Default for Mesh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Mesh {
     pub fn new() -> Self {
         Self {
@@ -59,14 +66,18 @@ impl Mesh {
             }
         }
 
-        if self.vertices.len() == 0 || self.triangles.len() == 0 {
-            let no_3d_data_error = io::Error::new(io::ErrorKind::Other, "No 3D data found.");
+        if self.vertices.is_empty() || self.triangles.is_empty() {
+            let no_3d_data_error = io::Error::other("No 3D data found.");
             return Err(no_3d_data_error)
         }
 
         Ok(())
     }
 
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
     // Note that we use read-only borrow here
     // because we don't need to edit anything really
     pub fn vertices(&self) -> &Vec<Vector3D> {
@@ -76,6 +87,25 @@ impl Mesh {
     pub fn triangles(&self) -> &Vec<[usize; 3]> {
         &self.triangles
     }
+
+    // Ray must be in object space
+    // Returns the closest triangle's index along with the hit data
+    pub fn raycast(&self, ray: &Ray) -> Option<(usize, TriangleHit)> {
+        let mut closest: Option<(usize, TriangleHit)> = None;
+
+        for (i, triangle) in self.triangles.iter().enumerate() {
+            let hit = match ray.intersect_triangle(triangle.map(|index| self.vertices[index])) {
+                Some(hit) => hit,
+                None => continue
+            };
+
+            if closest.is_none_or(|(_, closest_hit)| hit.distance < closest_hit.distance) {
+                closest = Some((i, hit));
+            }
+        }
+
+        closest
+    }
 }
 
 #[cfg(test)]
@@ -88,4 +118,24 @@ mod tests {
         assert_eq!(mesh.vertices.len(), 0);
         assert_eq!(mesh.triangles.len(), 0);
     }
+
+    #[test]
+    fn raycast_closest_triangle() {
+        let mut mesh = Mesh::new();
+        // Two stacked quads' worth of triangles, the far one first
+        mesh.vertices = vec![
+            Vector3D::new(-1, -1, 8), Vector3D::new(1, -1, 8), Vector3D::new(0, 1, 8),
+            Vector3D::new(-1, -1, 4), Vector3D::new(1, -1, 4), Vector3D::new(0, 1, 4),
+        ];
+        mesh.triangles = vec![[0, 1, 2], [3, 4, 5]];
+
+        let ray = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 0, 1));
+        let (triangle_index, hit) = mesh.raycast(&ray).unwrap();
+
+        assert_eq!(triangle_index, 1);
+        assert_eq!(hit.distance, 4.0);
+
+        let miss = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 0, -1));
+        assert_eq!(mesh.raycast(&miss), None);
+    }
 }
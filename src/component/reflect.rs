@@ -0,0 +1,366 @@
+use core::fmt;
+
+use crate::math_utils::vector3d::Vector3D;
+use crate::renderer::Color;
+
+
+// Runtime view of a struct's fields, so that tools like the editor
+// can list and edit them without knowing the concrete type.
+// Normally generated through #[derive(Component)] or #[derive(Reflect)]
+pub trait Reflect {
+    fn fields(&self) -> &'static [FieldInfo];
+    fn get_field(&self, name: &str) -> Option<FieldValue>;
+    fn set_field(&mut self, name: &str, value: FieldValue) -> Result<(), ReflectError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Integer,
+    Float,
+    Bool,
+    String,
+    Vector3D,
+    Color
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    // The type as written in the struct, eg. "u8" or "f32"
+    pub type_name: &'static str,
+    pub kind: FieldKind
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Integer(i128),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Vector3D(Vector3D),
+    Color(Color)
+}
+
+impl FieldValue {
+    pub fn kind(&self) -> FieldKind {
+        match self {
+            Self::Integer(_) => FieldKind::Integer,
+            Self::Float(_) => FieldKind::Float,
+            Self::Bool(_) => FieldKind::Bool,
+            Self::String(_) => FieldKind::String,
+            Self::Vector3D(_) => FieldKind::Vector3D,
+            Self::Color(_) => FieldKind::Color
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectError {
+    UnknownField(String),
+    TypeMismatch { field: String, expected: FieldKind, found: FieldKind },
+    OutOfRange { field: String, value: i128 }
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownField(field) => write!(f, "No reflected field named '{}'", field),
+            Self::TypeMismatch { field, expected, found } =>
+                write!(f, "Field '{}' expects {:?}, got {:?}", field, expected, found),
+            Self::OutOfRange { field, value } =>
+                write!(f, "Value {} is out of range for field '{}'", value, field)
+        }
+    }
+}
+
+// Conversion between a concrete field type and FieldValue
+// The derive relies on this to read and write each field
+pub trait ReflectValue: Sized {
+    const KIND: FieldKind;
+    fn to_field_value(&self) -> FieldValue;
+    fn from_field_value(value: FieldValue, field: &str) -> Result<Self, ReflectError>;
+}
+
+fn type_mismatch(field: &str, expected: FieldKind, value: &FieldValue) -> ReflectError {
+    ReflectError::TypeMismatch {
+        field: field.to_string(),
+        expected,
+        found: value.kind()
+    }
+}
+
+macro_rules! impl_reflect_integer {
+    ($($t:ty),*) => {
+        $(
+        impl ReflectValue for $t {
+            const KIND: FieldKind = FieldKind::Integer;
+
+            fn to_field_value(&self) -> FieldValue {
+                // Every integer type up to 64 bits fits, so this never truncates
+                FieldValue::Integer(*self as i128)
+            }
+
+            fn from_field_value(value: FieldValue, field: &str) -> Result<Self, ReflectError> {
+                match value {
+                    FieldValue::Integer(num) => <$t>::try_from(num).map_err(|_| {
+                        ReflectError::OutOfRange { field: field.to_string(), value: num }
+                    }),
+                    other => Err(type_mismatch(field, Self::KIND, &other))
+                }
+            }
+        }
+        )*
+    };
+}
+
+impl_reflect_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_reflect_variant {
+    ($t:ty, $variant:ident, $to:expr, $from:expr) => {
+        impl ReflectValue for $t {
+            const KIND: FieldKind = FieldKind::$variant;
+
+            fn to_field_value(&self) -> FieldValue {
+                FieldValue::$variant($to(self))
+            }
+
+            fn from_field_value(value: FieldValue, field: &str) -> Result<Self, ReflectError> {
+                match value {
+                    FieldValue::$variant(inner) => Ok($from(inner)),
+                    other => Err(type_mismatch(field, Self::KIND, &other))
+                }
+            }
+        }
+    };
+}
+
+impl_reflect_variant!(f32, Float, |x: &f32| *x as f64, |x: f64| x as f32);
+impl_reflect_variant!(f64, Float, |x: &f64| *x, |x: f64| x);
+impl_reflect_variant!(bool, Bool, |x: &bool| *x, |x: bool| x);
+impl_reflect_variant!(String, String, |x: &String| x.clone(), |x: String| x);
+impl_reflect_variant!(Vector3D, Vector3D, |x: &Vector3D| *x, |x: Vector3D| x);
+impl_reflect_variant!(Color, Color, |x: &Color| *x, |x: Color| x);
+
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use super::*;
+    use crate::component::{Component, ComponentType};
+    use crate::component::mesh::Mesh;
+    use lib_derive::Reflect;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Component, ComponentType)]
+    struct Spinner {
+        speed: f32,
+        turns: u8,
+        offset: i64,
+        enabled: bool,
+        label: String,
+        axis: Vector3D,
+        tint: Color,
+        #[reflect(skip)]
+        hidden: f64,
+        history: Vec<f64>
+    }
+
+    fn spinner() -> Spinner {
+        Spinner {
+            speed: 1.5,
+            turns: 3,
+            offset: -20,
+            enabled: true,
+            label: "Spin".to_string(),
+            axis: Vector3D::new(0, 1, 0),
+            tint: Color::new(10, 20, 30),
+            hidden: 0.0,
+            history: vec![]
+        }
+    }
+
+    #[test]
+    fn list_user_component_fields() {
+        let component: Box<dyn Component> = Box::new(spinner());
+        let fields = component.as_reflect().unwrap().fields();
+
+        let names = fields.iter().map(|f| f.name).collect::<Vec<&str>>();
+        assert_eq!(names, ["speed", "turns", "offset", "enabled", "label", "axis", "tint"]);
+
+        assert_eq!(fields[0].type_name, "f32");
+        assert_eq!(fields[0].kind, FieldKind::Float);
+        assert_eq!(fields[1].type_name, "u8");
+        assert_eq!(fields[1].kind, FieldKind::Integer);
+        assert_eq!(fields[5].kind, FieldKind::Vector3D);
+        assert_eq!(fields[6].kind, FieldKind::Color);
+    }
+
+    #[test]
+    fn get_user_component_fields() {
+        let component: Box<dyn Component> = Box::new(spinner());
+        let reflect = component.as_reflect().unwrap();
+
+        assert_eq!(reflect.get_field("speed"), Some(FieldValue::Float(1.5)));
+        assert_eq!(reflect.get_field("offset"), Some(FieldValue::Integer(-20)));
+        assert_eq!(reflect.get_field("enabled"), Some(FieldValue::Bool(true)));
+        assert_eq!(reflect.get_field("label"), Some(FieldValue::String("Spin".to_string())));
+        assert_eq!(reflect.get_field("axis"), Some(FieldValue::Vector3D(Vector3D::new(0, 1, 0))));
+        assert_eq!(reflect.get_field("tint"), Some(FieldValue::Color(Color::new(10, 20, 30))));
+        assert_eq!(reflect.get_field("hidden"), None);
+        assert_eq!(reflect.get_field("history"), None);
+    }
+
+    #[test]
+    fn set_user_component_fields() {
+        let mut component: Box<dyn Component> = Box::new(spinner());
+        let reflect = component.as_reflect_mut().unwrap();
+
+        reflect.set_field("turns", FieldValue::Integer(7)).unwrap();
+        reflect.set_field("axis", FieldValue::Vector3D(Vector3D::new(1, 0, 0))).unwrap();
+        reflect.set_field("label", FieldValue::String("Spun".to_string())).unwrap();
+
+        let concrete = component.as_any().downcast_ref::<Spinner>().unwrap();
+        assert_eq!(concrete.turns, 7);
+        assert_eq!(concrete.axis, Vector3D::new(1, 0, 0));
+        assert_eq!(concrete.label, "Spun");
+    }
+
+    #[test]
+    fn set_field_errors() {
+        let mut component = spinner();
+
+        assert_eq!(
+            component.set_field("turns", FieldValue::Integer(300)),
+            Err(ReflectError::OutOfRange { field: "turns".to_string(), value: 300 })
+        );
+        assert_eq!(
+            component.set_field("speed", FieldValue::Bool(false)),
+            Err(ReflectError::TypeMismatch {
+                field: "speed".to_string(),
+                expected: FieldKind::Float,
+                found: FieldKind::Bool
+            })
+        );
+        assert_eq!(
+            component.set_field("hidden", FieldValue::Float(1.0)),
+            Err(ReflectError::UnknownField("hidden".to_string()))
+        );
+        assert_eq!(component.turns, 3);
+    }
+
+    #[test]
+    fn reflect_mesh() {
+        let mut mesh: Box<dyn Component> = Box::new(Mesh::new());
+        let fields = mesh.as_reflect().unwrap().fields();
+
+        // Vertex and triangle lists aren't editable field types
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "src");
+        assert_eq!(fields[0].kind, FieldKind::String);
+
+        mesh.as_reflect_mut().unwrap()
+            .set_field("src", FieldValue::String("cube.obj".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            mesh.as_reflect().unwrap().get_field("src"),
+            Some(FieldValue::String("cube.obj".to_string()))
+        );
+    }
+
+    // User types that happen to share the engine types' names
+    mod paint {
+        use super::{FieldKind, FieldValue, ReflectError, ReflectValue};
+        use lib_derive::Reflect;
+
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct Color(pub u8);
+
+        // Picks its own way to be edited
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct Vector3D(pub f64);
+
+        impl ReflectValue for Vector3D {
+            const KIND: FieldKind = FieldKind::Float;
+
+            fn to_field_value(&self) -> FieldValue {
+                FieldValue::Float(self.0)
+            }
+
+            fn from_field_value(value: FieldValue, field: &str) -> Result<Self, ReflectError> {
+                f64::from_field_value(value, field).map(Vector3D)
+            }
+        }
+
+        #[derive(Reflect)]
+        pub struct Brush {
+            #[reflect(skip)]
+            pub color: Color,
+            pub size: Vector3D,
+            pub engine_color: crate::renderer::Color
+        }
+    }
+
+    #[test]
+    fn reflect_same_named_user_types() {
+        let mut brush = paint::Brush {
+            color: paint::Color(3),
+            size: paint::Vector3D(2.0),
+            engine_color: Color::new(255, 255, 255)
+        };
+
+        let fields = brush.fields();
+        let names = fields.iter().map(|f| f.name).collect::<Vec<&str>>();
+        assert_eq!(names, ["size", "engine_color"]);
+        assert_eq!(fields[0].type_name, "Vector3D");
+        assert_eq!(fields[0].kind, FieldKind::Float);
+        assert_eq!(fields[1].type_name, "crate::renderer::Color");
+        assert_eq!(fields[1].kind, FieldKind::Color);
+
+        brush.set_field("size", FieldValue::Float(4.0)).unwrap();
+        assert_eq!(brush.size, paint::Vector3D(4.0));
+        assert_eq!(brush.get_field("color"), None);
+        assert_eq!(brush.color, paint::Color(3));
+    }
+
+    #[derive(Reflect)]
+    struct Counters {
+        total: u64,
+        index: usize
+    }
+
+    #[test]
+    fn reflect_large_unsigned_values() {
+        let mut counters = Counters { total: u64::MAX, index: 0 };
+        assert_eq!(counters.get_field("total"), Some(FieldValue::Integer(u64::MAX as i128)));
+
+        // Reading a value back and writing it again doesn't change it
+        let total = counters.get_field("total").unwrap();
+        counters.set_field("total", total).unwrap();
+        assert_eq!(counters.total, u64::MAX);
+
+        counters.set_field("index", FieldValue::Integer(i64::MAX as i128 + 1)).unwrap();
+        assert_eq!(counters.index as u64, i64::MAX as u64 + 1);
+        assert_eq!(
+            counters.set_field("index", FieldValue::Integer(-1)),
+            Err(ReflectError::OutOfRange { field: "index".to_string(), value: -1 })
+        );
+    }
+
+    #[derive(Reflect)]
+    struct Settings {
+        volume: f64,
+        muted: bool
+    }
+
+    #[test]
+    fn reflect_plain_struct() {
+        let mut settings = Settings { volume: 0.5, muted: false };
+        settings.set_field("muted", FieldValue::Bool(true)).unwrap();
+
+        assert_eq!(settings.fields().len(), 2);
+        assert_eq!(settings.get_field("volume"), Some(FieldValue::Float(0.5)));
+        assert!(settings.muted);
+    }
+}
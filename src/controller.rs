@@ -0,0 +1,248 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::transform::Transform;
+use crate::math_utils::{vector3d::Vector3D, quaternion::Quaternion};
+
+// Camera controllers, turning mouse and keyboard deltas into a Transform
+// Mouse deltas are in pixels, with +X to the right and +Y going down like winit's
+
+// Stops just short of straight up or down, where yaw stops meaning anything
+const MAX_PITCH: f64 = FRAC_PI_2 - 0.01;
+
+// Circles around a target, always looking at it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitController {
+    pub target: Vector3D,
+    pub distance: f64,
+    pub min_distance: f64,
+    pub max_distance: f64,
+    // Radians per pixel dragged
+    pub rotate_speed: f64,
+    // Fraction of the distance covered per wheel step
+    pub zoom_speed: f64,
+    yaw: f64,
+    pitch: f64
+}
+
+impl OrbitController {
+    pub fn new(target: Vector3D, distance: f64) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            rotate_speed: 0.01,
+            zoom_speed: 0.1,
+            yaw: 0.0,
+            pitch: 0.0
+        }
+    }
+
+    // Picks up from wherever the transform already is, so switching controllers doesn't jump
+    pub fn from_transform(transform: &Transform, target: Vector3D) -> Self {
+        let offset = target - transform.position();
+        let (yaw, pitch) = yaw_pitch_from_forward(offset);
+
+        Self {
+            yaw,
+            pitch,
+            ..Self::new(target, offset.length())
+        }
+    }
+
+    // Dragging right moves the camera left around the target, so the target seems to turn right
+    pub fn rotate(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.yaw -= mouse_dx * self.rotate_speed;
+        self.pitch = (self.pitch - mouse_dy * self.rotate_speed).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // Positive wheel steps move closer
+    pub fn zoom(&mut self, wheel_steps: f64) {
+        let distance = self.distance * (1.0 - self.zoom_speed).powf(wheel_steps);
+        self.distance = distance.clamp(self.min_distance, self.max_distance);
+    }
+
+    // Slides the target along the camera's right and up,
+    // scaled by the distance so the target keeps up with the cursor
+    pub fn pan(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        let rotation = yaw_pitch_rotation(self.yaw, self.pitch);
+        let right = rotation.rotate_vector(Vector3D::new(1, 0, 0));
+        let up = rotation.rotate_vector(Vector3D::new(0, 1, 0));
+        let scale = self.distance * self.rotate_speed * 0.1;
+
+        self.target += (right * -mouse_dx + up * mouse_dy) * scale;
+    }
+
+    pub fn apply(&self, transform: &mut Transform) {
+        let rotation = yaw_pitch_rotation(self.yaw, self.pitch);
+        let forward = rotation.rotate_vector(Vector3D::new(0, 0, 1));
+
+        transform.set_position(self.target - forward * self.distance);
+        transform.set_rotation(rotation);
+    }
+}
+
+// Free look, moving along wherever the camera faces
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlyController {
+    // World units per second
+    pub move_speed: f64,
+    // Radians per pixel
+    pub look_speed: f64,
+    yaw: f64,
+    pitch: f64
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlyController {
+    pub fn new() -> Self {
+        Self {
+            move_speed: 5.0,
+            look_speed: 0.005,
+            yaw: 0.0,
+            pitch: 0.0
+        }
+    }
+
+    pub fn from_transform(transform: &Transform) -> Self {
+        let (yaw, pitch) = yaw_pitch_from_forward(transform.local_forward());
+
+        Self {
+            yaw,
+            pitch,
+            ..Self::new()
+        }
+    }
+
+    // Moving the mouse right turns right, moving it down looks down
+    pub fn look(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.yaw -= mouse_dx * self.look_speed;
+        self.pitch = (self.pitch - mouse_dy * self.look_speed).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // Movement is in the camera's own axes (X right, Y up, Z forward), eg. from WASD
+    // It's normalized, so going diagonally isn't any faster
+    pub fn update(&self, transform: &mut Transform, movement: Vector3D, delta_time: f64) {
+        let rotation = yaw_pitch_rotation(self.yaw, self.pitch);
+        let movement = rotation.rotate_vector(movement.normalize());
+
+        transform.set_rotation(rotation);
+        transform.set_position(transform.position() + movement * self.move_speed * delta_time);
+    }
+}
+
+// Pitch around the local X first, then yaw around the world Y, so there's never any roll
+fn yaw_pitch_rotation(yaw: f64, pitch: f64) -> Quaternion {
+    Quaternion::from_axis_angle(Vector3D::new(1, 0, 0), pitch)
+    * Quaternion::from_axis_angle(Vector3D::new(0, 1, 0), yaw)
+}
+
+// Inverse of yaw_pitch_rotation() applied to +Z
+fn yaw_pitch_from_forward(forward: Vector3D) -> (f64, f64) {
+    let forward = forward.normalize();
+    let pitch = forward.y.clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH);
+    let yaw = (-forward.x).atan2(forward.z);
+
+    (yaw, pitch)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::round_place;
+
+    fn round_vector3d(vector: Vector3D) -> Vector3D {
+        Vector3D::new(round_place(vector.x, 6), round_place(vector.y, 6), round_place(vector.z, 6))
+    }
+
+    #[test]
+    fn orbit_keeps_looking_at_target() {
+        let target = Vector3D::new(1, 2, 3);
+        let mut orbit = OrbitController::new(target, 4.0);
+        let mut transform = Transform::new();
+
+        orbit.rotate(120.0, -45.0);
+        orbit.apply(&mut transform);
+
+        assert_eq!(round_place(transform.position().distance(target), 6), 4.0);
+        let to_target = (target - transform.position()).normalize();
+        assert_eq!(round_vector3d(transform.local_forward()), round_vector3d(to_target));
+        // No roll, the right vector stays level
+        assert_eq!(round_place(transform.local_right().y, 6), 0.0);
+    }
+
+    #[test]
+    fn orbit_drag_right_moves_camera_left() {
+        let mut orbit = OrbitController::new(Vector3D::new(0, 0, 0), 5.0);
+        let mut transform = Transform::new();
+
+        orbit.rotate(10.0, 0.0);
+        orbit.apply(&mut transform);
+        assert!(transform.position().x < 0.0);
+
+        // Dragging down looks down from above
+        orbit.rotate(0.0, 10.0);
+        orbit.apply(&mut transform);
+        assert!(transform.position().y > 0.0);
+        assert!(transform.local_forward().y < 0.0);
+    }
+
+    #[test]
+    fn orbit_pitch_and_zoom_are_clamped() {
+        let mut orbit = OrbitController::new(Vector3D::new(0, 0, 0), 5.0);
+        orbit.rotate(0.0, 100000.0);
+        orbit.zoom(1000.0);
+
+        let mut transform = Transform::new();
+        orbit.apply(&mut transform);
+        assert!(transform.local_forward().y > -1.0);
+        assert_eq!(orbit.distance, orbit.min_distance);
+
+        orbit.zoom(-1000.0);
+        assert_eq!(orbit.distance, orbit.max_distance);
+    }
+
+    #[test]
+    fn orbit_from_transform_doesnt_jump() {
+        let mut transform = Transform::new();
+        transform.set_position(Vector3D::new(3, 4, -2));
+        transform.look_at(Vector3D::new(0, 0, 5), Vector3D::new(0, 1, 0));
+        let before = transform.position();
+
+        OrbitController::from_transform(&transform, Vector3D::new(0, 0, 5)).apply(&mut transform);
+        assert_eq!(round_vector3d(transform.position()), round_vector3d(before));
+    }
+
+    #[test]
+    fn fly_moves_where_it_looks() {
+        let mut fly = FlyController::new();
+        let mut transform = Transform::new();
+
+        fly.look(-FRAC_PI_2 / fly.look_speed, 0.0);
+        fly.update(&mut transform, Vector3D::new(0, 0, 1), 2.0);
+        // A quarter turn left, then 2 seconds forward
+        assert_eq!(round_vector3d(transform.position()), Vector3D::new(-10, 0, 0));
+
+        // Diagonals are as fast as straight lines
+        let mut transform = Transform::new();
+        FlyController::new().update(&mut transform, Vector3D::new(1, 0, 1), 1.0);
+        assert_eq!(round_place(transform.position().length(), 6), 5.0);
+    }
+
+    #[test]
+    fn fly_from_transform_keeps_direction() {
+        let mut transform = Transform::new();
+        transform.look_at(Vector3D::new(1, 1, 1), Vector3D::new(0, 1, 0));
+        let forward = transform.local_forward();
+
+        FlyController::from_transform(&transform).update(&mut transform, Vector3D::new(0, 0, 0), 1.0);
+        assert_eq!(round_vector3d(transform.local_forward()), round_vector3d(forward));
+        assert_eq!(transform.position(), Vector3D::new(0, 0, 0));
+    }
+}
@@ -0,0 +1,411 @@
+use std::f64::consts::PI;
+use std::sync::Mutex;
+
+use crate::renderer::{Renderer, Color};
+use crate::object::Camera;
+use crate::transform::Transform;
+use crate::math_utils::vector3d::Vector3D;
+
+// Segments per circle of a wire sphere
+const SPHERE_SEGMENTS: usize = 24;
+// How far in front of a surface a line may be hidden and still drawn, in screen space depth
+// Lines along a mesh's edges would flicker in and out without it
+const DEPTH_BIAS: f64 = 1e-4;
+
+const AXIS_X: (u8, u8, u8) = (230, 60, 60);
+const AXIS_Y: (u8, u8, u8) = (60, 230, 60);
+const AXIS_Z: (u8, u8, u8) = (60, 100, 240);
+
+// Shapes queued by the free functions below, drawn and emptied by render()
+static QUEUE: Mutex<DebugShapes> = Mutex::new(DebugShapes::new());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugStyle {
+    pub color: Color,
+    // Hidden behind whatever the main pass drew in front of it, on by default
+    pub depth_test: bool
+}
+
+impl DebugStyle {
+    pub fn new(color: Color) -> Self {
+        Self { color, depth_test: true }
+    }
+
+    // Drawn over everything
+    pub fn on_top(mut self) -> Self {
+        self.depth_test = false;
+        self
+    }
+}
+
+// Every shape is broken down into world space lines when queued
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugLine {
+    pub start: Vector3D,
+    pub end: Vector3D,
+    pub style: DebugStyle
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DebugShapes {
+    lines: Vec<DebugLine>
+}
+
+impl DebugShapes {
+    pub const fn new() -> Self {
+        Self { lines: Vec::new() }
+    }
+
+    pub fn lines(&self) -> &[DebugLine] {
+        &self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn line(&mut self, start: Vector3D, end: Vector3D, style: DebugStyle) {
+        self.lines.push(DebugLine { start, end, style });
+    }
+
+    // Axis aligned box, min and max are opposite corners
+    pub fn aabb(&mut self, min: Vector3D, max: Vector3D, style: DebugStyle) {
+        let corner = |i: usize| Vector3D {
+            x: if i & 1 == 0 { min.x } else { max.x },
+            y: if i & 2 == 0 { min.y } else { max.y },
+            z: if i & 4 == 0 { min.z } else { max.z }
+        };
+
+        // Corners one bit apart share an edge
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corner(i), corner(i | bit), style);
+                }
+            }
+        }
+    }
+
+    // One circle around each world axis
+    pub fn wire_sphere(&mut self, center: Vector3D, radius: f64, style: DebugStyle) {
+        let axes = [
+            (Vector3D::new(1, 0, 0), Vector3D::new(0, 1, 0)),
+            (Vector3D::new(0, 1, 0), Vector3D::new(0, 0, 1)),
+            (Vector3D::new(0, 0, 1), Vector3D::new(1, 0, 0))
+        ];
+
+        for (u, v) in axes {
+            let point = |i: usize| {
+                let angle = i as f64 / SPHERE_SEGMENTS as f64 * 2.0 * PI;
+                center + (u * angle.cos() + v * angle.sin()) * radius
+            };
+
+            for i in 0..SPHERE_SEGMENTS {
+                self.line(point(i), point(i + 1), style);
+            }
+        }
+    }
+
+    // Red, green and blue lines along the transform's right, up and forward
+    // Always on top, the gizmo is no use once hidden inside its object
+    pub fn axes(&mut self, transform: &Transform, size: f64) {
+        let origin = transform.position();
+        let directions = [
+            (transform.local_right(), AXIS_X),
+            (transform.local_up(), AXIS_Y),
+            (transform.local_forward(), AXIS_Z)
+        ];
+
+        for (direction, (r, g, b)) in directions {
+            let style = DebugStyle::new(Color::new(r, g, b)).on_top();
+            self.line(origin, origin + direction * size, style);
+        }
+    }
+
+    // The volume the camera sees, from its near to its far plane
+    pub fn frustum(&mut self, camera: &mut Camera, style: DebugStyle) {
+        // Screen space corners back to camera space, then out into the world
+        let inverse_projection = match camera.projection_matrix().inverse() {
+            Ok(matrix) => matrix,
+            Err(_) => return
+        };
+        let camera_matrix = camera.transform.matrix();
+        let corner = |x: f64, y: f64, depth: f64| {
+            let in_cam = inverse_projection.transform_point(Vector3D::new(x, y, depth));
+            camera_matrix.transform_point(in_cam)
+        };
+
+        let screen_corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        let near = screen_corners.map(|(x, y)| corner(x, y, 0.0));
+        let far = screen_corners.map(|(x, y)| corner(x, y, 1.0));
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(near[i], near[next], style);
+            self.line(far[i], far[next], style);
+            self.line(near[i], far[i], style);
+        }
+    }
+
+    // Call after the main pass, so depth tested lines can be hidden by the scene
+    pub fn draw(&self, renderer: &mut Renderer, camera: &mut Camera) {
+        for line in &self.lines {
+            draw_line(renderer, camera, line);
+        }
+    }
+}
+
+pub fn line(start: Vector3D, end: Vector3D, style: DebugStyle) {
+    queue().line(start, end, style);
+}
+
+pub fn aabb(min: Vector3D, max: Vector3D, style: DebugStyle) {
+    queue().aabb(min, max, style);
+}
+
+pub fn wire_sphere(center: Vector3D, radius: f64, style: DebugStyle) {
+    queue().wire_sphere(center, radius, style);
+}
+
+pub fn axes(transform: &Transform, size: f64) {
+    queue().axes(transform, size);
+}
+
+pub fn frustum(camera: &mut Camera, style: DebugStyle) {
+    queue().frustum(camera, style);
+}
+
+// Throws away everything queued, for frames that don't get rendered
+pub fn clear() {
+    queue().clear();
+}
+
+// Draws everything queued since the last call, then empties the queue
+pub fn render(renderer: &mut Renderer, camera: &mut Camera) {
+    let shapes = std::mem::take(&mut *queue());
+    shapes.draw(renderer, camera);
+}
+
+// A panic while holding the lock can only have left some lines half queued
+fn queue() -> std::sync::MutexGuard<'static, DebugShapes> {
+    QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn draw_line(renderer: &mut Renderer, camera: &mut Camera, line: &DebugLine) {
+    let view_matrix = camera.view_matrix();
+    let near_clip_distance = camera.near_clip_distance();
+    let mut start = view_matrix.transform_point(line.start);
+    let mut end = view_matrix.transform_point(line.end);
+
+    // Cut off whatever is behind the near plane
+    if start.z < near_clip_distance && end.z < near_clip_distance { return }
+    if start.z < near_clip_distance {
+        start = start.lerp(end, (near_clip_distance - start.z) / (end.z - start.z));
+    } else if end.z < near_clip_distance {
+        end = end.lerp(start, (near_clip_distance - end.z) / (start.z - end.z));
+    }
+
+    let start = camera.project_to_screen_space(start);
+    let end = camera.project_to_screen_space(end);
+    let (start_x, start_y) = renderer.to_pixel_coords(start);
+    let (end_x, end_y) = renderer.to_pixel_coords(end);
+
+    let (dx, dy) = (end_x - start_x, end_y - start_y);
+    let steps = dx.abs().max(dy.abs());
+    let (t_min, t_max) = match clip_to_screen(renderer, (start_x, start_y), (dx, dy)) {
+        Some(range) => range,
+        None => return
+    };
+
+    // Depth is linear in screen space, same as the triangles
+    let first = (t_min * steps as f64).floor() as isize;
+    let last = (t_max * steps as f64).ceil() as isize;
+    for step in first..=last {
+        let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+        let x = start_x + (dx as f64 * t).round() as isize;
+        let y = start_y + (dy as f64 * t).round() as isize;
+        if x < 0 || y < 0 { continue }
+
+        let (x, y) = (x as usize, y as usize);
+        if line.style.depth_test {
+            let depth = start.z + (end.z - start.z) * t;
+            match renderer.depth_at(x, y) {
+                Some(surface) if depth <= surface + DEPTH_BIAS => (),
+                _ => continue
+            }
+        }
+        renderer.plot_pixel(x, y, line.style.color);
+    }
+}
+
+// Part of the line start + t * delta, 0 <= t <= 1, that's on screen, or None if it's all off
+// Saves stepping through thousands of pixels for lines passing right by the camera
+fn clip_to_screen(renderer: &Renderer, start: (isize, isize), delta: (isize, isize)) -> Option<(f64, f64)> {
+    let (mut t_min, mut t_max) = (0.0_f64, 1.0_f64);
+    let bounds = [
+        (start.0 as f64, delta.0 as f64, (renderer.width() as f64 - 1.0).max(0.0)),
+        (start.1 as f64, delta.1 as f64, (renderer.height() as f64 - 1.0).max(0.0))
+    ];
+
+    for (position, delta, max) in bounds {
+        if delta == 0.0 {
+            if position < 0.0 || position > max { return None }
+            continue;
+        }
+
+        let (t0, t1) = ((0.0 - position) / delta, (max - position) / delta);
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+    }
+
+    if t_min > t_max { None } else { Some((t_min, t_max)) }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::{Object, AspectRatio};
+    use crate::scene::Scene;
+    use crate::component::mesh::Mesh;
+    use std::{env, fs};
+
+    fn red() -> DebugStyle {
+        DebugStyle::new(Color::new(255, 0, 0))
+    }
+
+    fn camera() -> Camera {
+        Camera::new(1, 30, 90, AspectRatio(1.0, 1.0))
+    }
+
+    #[test]
+    fn aabb_has_twelve_edges() {
+        let mut shapes = DebugShapes::new();
+        let (min, max) = (Vector3D::new(-1, -2, -3), Vector3D::new(1, 2, 3));
+        shapes.aabb(min, max, red());
+
+        assert_eq!(shapes.lines().len(), 12);
+        for line in shapes.lines() {
+            // Every edge runs along exactly one axis
+            let diff = line.end - line.start;
+            let axes_moved = [diff.x, diff.y, diff.z].iter().filter(|&&d| d != 0.0).count();
+            assert_eq!(axes_moved, 1);
+        }
+    }
+
+    #[test]
+    fn sphere_points_are_on_the_surface() {
+        let mut shapes = DebugShapes::new();
+        let center = Vector3D::new(1, 2, 3);
+        shapes.wire_sphere(center, 2.0, red());
+
+        assert_eq!(shapes.lines().len(), SPHERE_SEGMENTS * 3);
+        assert!(shapes.lines().iter().all(|line| (line.start.distance(center) - 2.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn axes_follow_the_transform() {
+        let mut transform = Transform::new();
+        transform.set_position(Vector3D::new(1, 0, 0));
+        transform.rotate(0, 90, 0, crate::transform::Space::World);
+
+        let mut shapes = DebugShapes::new();
+        shapes.axes(&transform, 2.0);
+
+        let lines = shapes.lines();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.start == transform.position() && !line.style.depth_test));
+        let forward_end = transform.position() + transform.local_forward() * 2.0;
+        assert!(lines[2].end.distance(forward_end) < 1e-9);
+    }
+
+    #[test]
+    fn frustum_reaches_the_far_plane() {
+        let mut camera = camera();
+        let mut shapes = DebugShapes::new();
+        shapes.frustum(&mut camera, red());
+
+        assert_eq!(shapes.lines().len(), 12);
+        let depths = shapes.lines().iter().flat_map(|line| [line.start.z, line.end.z]);
+        let (near, far) = depths.fold((f64::MAX, f64::MIN), |(near, far), z| (near.min(z), far.max(z)));
+        assert!((near - 1.0).abs() < 1e-9);
+        assert!((far - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lines_behind_the_camera_are_clipped() {
+        let mut renderer = Renderer::new(20, 20);
+        let mut camera = camera();
+        let mut shapes = DebugShapes::new();
+        shapes.line(Vector3D::new(0, 0, -5), Vector3D::new(0, 0, -1), red());
+        shapes.draw(&mut renderer, &mut camera);
+        assert!(renderer.buffer().iter().all(|&pixel| pixel == 0));
+
+        // Crossing the near plane, only the front part is drawn
+        shapes.clear();
+        shapes.line(Vector3D::new(0, 0, -5), Vector3D::new(0, 0, 5), red());
+        shapes.draw(&mut renderer, &mut camera);
+        assert!(renderer.buffer().iter().any(|&pixel| pixel != 0));
+    }
+
+    #[test]
+    fn depth_test_hides_lines_behind_meshes() {
+        let obj_path = env::temp_dir()
+            .join(format!("zesty_{}_debug_draw.obj", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        // A big quad facing the camera
+        fs::write(&obj_path, "v -5 -5 0\nv -5 5 0\nv 5 5 0\nv 5 -5 0\nf 1 2 3\nf 1 3 4\n").unwrap();
+        let mut mesh = Mesh::new();
+        mesh.load_obj(&obj_path).unwrap();
+        fs::remove_file(obj_path).unwrap();
+
+        let mut object = Object::new();
+        object.transform.set_position(Vector3D::new(0, 0, 5));
+        object.add_component(mesh);
+        let mut scene = Scene { objects: vec![object], camera: camera() };
+
+        let mut renderer = Renderer::new(40, 40);
+        renderer.render(&mut scene);
+        let center = 20 + 20 * 40;
+        let quad_color = renderer.buffer()[center];
+        assert_ne!(quad_color, 0);
+
+        let behind = (Vector3D::new(-1, 0, 10), Vector3D::new(1, 0, 10));
+        let in_front = (Vector3D::new(0, -1, 3), Vector3D::new(0, 1, 3));
+        let red_pixel = Color::new(255, 0, 0).rgb_u32();
+
+        let mut shapes = DebugShapes::new();
+        shapes.line(behind.0, behind.1, red());
+        shapes.draw(&mut renderer, &mut scene.camera);
+        assert!(!renderer.buffer().contains(&red_pixel));
+
+        shapes.clear();
+        shapes.line(behind.0, behind.1, red().on_top());
+        shapes.draw(&mut renderer, &mut scene.camera);
+        assert!(renderer.buffer().contains(&red_pixel));
+
+        renderer.render(&mut scene);
+        shapes.clear();
+        shapes.line(in_front.0, in_front.1, red());
+        shapes.draw(&mut renderer, &mut scene.camera);
+        assert_eq!(renderer.buffer()[center], red_pixel);
+    }
+
+    #[test]
+    fn render_empties_the_queue() {
+        let mut renderer = Renderer::new(20, 20);
+        let mut camera = camera();
+        aabb(Vector3D::new(-1, -1, 4), Vector3D::new(1, 1, 6), red());
+        render(&mut renderer, &mut camera);
+        assert!(renderer.buffer().iter().any(|&pixel| pixel != 0));
+
+        renderer.clear_tmp_buffer();
+        render(&mut renderer, &mut camera);
+        assert!(renderer.buffer().iter().all(|&pixel| pixel == 0));
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+
+use winit::event::{WindowEvent, ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+// Trackpads scroll in pixels instead of lines, this is roughly one line
+const PIXELS_PER_WHEEL_STEP: f64 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Button {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisBinding {
+    // +1 while positive is held, -1 while negative is, 0 for both or neither
+    Buttons { positive: Button, negative: Button },
+    // Mouse movement this frame, in pixels
+    MouseX,
+    MouseY,
+    // Wheel steps this frame, positive is away from the user
+    Wheel
+}
+
+// Named actions and axes, so gameplay code can ask for "jump"
+// instead of caring which key that is
+//
+// Input map files are plain text, one binding per line:
+//
+//   action <name> <button>
+//   axis <name> <positive button> <negative button>
+//   axis <name> MouseX|MouseY|Wheel
+//
+// Buttons are winit key names (W, Space, LShift, Key1...) or MouseLeft/MouseRight/MouseMiddle
+// Binding the same name again adds to it rather than replacing it
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Button>>,
+    axes: HashMap<String, Vec<AxisBinding>>
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(file_path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut map = Self::new();
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let error = |message: &str| io::Error::new(
+                io::ErrorKind::InvalidData, format!("Line {}: {}", line_number + 1, message)
+            );
+            let button = |name: &str| parse_button(name).ok_or_else(|| error(&format!("Unknown button '{}'", name)));
+
+            let tokens = line.split_whitespace().collect::<Vec<&str>>();
+            match tokens.as_slice() {
+                ["action", name, button_name] => map.bind_action(name, button(button_name)?),
+                ["axis", name, "MouseX"] => map.bind_axis(name, AxisBinding::MouseX),
+                ["axis", name, "MouseY"] => map.bind_axis(name, AxisBinding::MouseY),
+                ["axis", name, "Wheel"] => map.bind_axis(name, AxisBinding::Wheel),
+                ["axis", name, positive, negative] => map.bind_axis(name, AxisBinding::Buttons {
+                    positive: button(positive)?,
+                    negative: button(negative)?
+                }),
+                ["action", ..] => return Err(error("Expected a name and a button")),
+                ["axis", ..] => return Err(error("Expected a name and 2 buttons, or MouseX/MouseY/Wheel")),
+                [keyword, ..] => return Err(error(&format!("Unknown statement '{}'", keyword))),
+                [] => unreachable!()
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn bind_action(&mut self, name: &str, button: Button) {
+        self.actions.entry(name.to_string()).or_default().push(button);
+    }
+
+    pub fn bind_axis(&mut self, name: &str, binding: AxisBinding) {
+        self.axes.entry(name.to_string()).or_default().push(binding);
+    }
+
+    pub fn action(&self, name: &str) -> &[Button] {
+        self.actions.get(name).map_or(&[], |buttons| buttons.as_slice())
+    }
+
+    pub fn axis(&self, name: &str) -> &[AxisBinding] {
+        self.axes.get(name).map_or(&[], |bindings| bindings.as_slice())
+    }
+}
+
+// The part of winit's window events that input cares about
+// Unlike WindowEvent these are plain data, so they can be recorded and played back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Pressed(Button),
+    Released(Button),
+    CursorMoved(f64, f64),
+    CursorLeft,
+    Wheel(f64),
+    FocusLost
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        let button_event = |button, state| match state {
+            ElementState::Pressed => InputEvent::Pressed(button),
+            ElementState::Released => InputEvent::Released(button)
+        };
+
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                input.virtual_keycode.map(|key| button_event(Button::Key(key), input.state))
+            },
+            WindowEvent::MouseInput { state, button, .. } => Some(button_event(Button::Mouse(*button), *state)),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved(position.x, position.y)),
+            WindowEvent::CursorLeft { .. } => Some(InputEvent::CursorLeft),
+            WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::Wheel(match delta {
+                MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                MouseScrollDelta::PixelDelta(position) => position.y / PIXELS_PER_WHEEL_STEP
+            })),
+            WindowEvent::Focused(false) => Some(InputEvent::FocusLost),
+            _ => None
+        }
+    }
+}
+
+// Keyboard and mouse state, fed with winit's window events
+// "Pressed" and "released" only last for the frame they happened in,
+// end_frame() has to be called once every frame to move on
+#[derive(Debug, Default)]
+pub struct Input {
+    pub map: InputMap,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    mouse_position: Option<(f64, f64)>,
+    mouse_delta: (f64, f64),
+    wheel_delta: f64
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            ..Self::default()
+        }
+    }
+
+    // Returns what the event meant to the input state, if anything, so it can be recorded
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Option<InputEvent> {
+        let event = InputEvent::from_window_event(event)?;
+        self.apply(event);
+        Some(event)
+    }
+
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            // Key repeat sends Pressed again, only the first one counts
+            InputEvent::Pressed(button) => if self.held.insert(button) {
+                self.pressed.insert(button);
+            },
+            InputEvent::Released(button) => if self.held.remove(&button) {
+                self.released.insert(button);
+            },
+            InputEvent::CursorMoved(x, y) => {
+                if let Some((last_x, last_y)) = self.mouse_position {
+                    self.mouse_delta.0 += x - last_x;
+                    self.mouse_delta.1 += y - last_y;
+                }
+                self.mouse_position = Some((x, y));
+            },
+            // Coming back in shouldn't count as a huge jump
+            InputEvent::CursorLeft => self.mouse_position = None,
+            InputEvent::Wheel(steps) => self.wheel_delta += steps,
+            // Releases that happen while unfocused never arrive, so nothing stays stuck down
+            InputEvent::FocusLost => self.released.extend(self.held.drain())
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.wheel_delta = 0.0;
+    }
+
+    pub fn is_held(&self, button: Button) -> bool {
+        self.held.contains(&button)
+    }
+
+    pub fn was_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn was_released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    // None until the cursor moves over the window
+    pub fn mouse_position(&self) -> Option<(f64, f64)> {
+        self.mouse_position
+    }
+
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    pub fn wheel_delta(&self) -> f64 {
+        self.wheel_delta
+    }
+
+    // Any of the action's buttons counts
+
+    pub fn action_held(&self, name: &str) -> bool {
+        self.map.action(name).iter().any(|&button| self.is_held(button))
+    }
+
+    pub fn action_pressed(&self, name: &str) -> bool {
+        self.map.action(name).iter().any(|&button| self.was_pressed(button))
+    }
+
+    pub fn action_released(&self, name: &str) -> bool {
+        self.map.action(name).iter().any(|&button| self.was_released(button))
+    }
+
+    // Sum of all the axis' bindings, 0 for unknown names
+    pub fn axis(&self, name: &str) -> f64 {
+        self.map.axis(name).iter().map(|binding| match *binding {
+            AxisBinding::Buttons { positive, negative } => {
+                self.is_held(positive) as i32 as f64 - self.is_held(negative) as i32 as f64
+            },
+            AxisBinding::MouseX => self.mouse_delta.0,
+            AxisBinding::MouseY => self.mouse_delta.1,
+            AxisBinding::Wheel => self.wheel_delta
+        }).sum()
+    }
+}
+
+// Key names are the same as winit's VirtualKeyCode variants
+// Only these can be bound or recorded, the rest are too keyboard specific to rely on
+macro_rules! key_names {
+    ($($key:ident),*) => {
+        fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None
+            }
+        }
+
+        fn key_name(key: VirtualKeyCode) -> Option<&'static str> {
+            match key {
+                $(VirtualKeyCode::$key => Some(stringify!($key)),)*
+                _ => None
+            }
+        }
+    };
+}
+
+key_names!(
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Escape, Tab, Space, Return, Back, Delete, Insert, Home, End, PageUp, PageDown,
+    Left, Right, Up, Down,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Minus, Equals, Comma, Period, Slash, Backslash, Semicolon, Apostrophe, Grave,
+    LBracket, RBracket
+);
+
+pub(crate) fn parse_button(name: &str) -> Option<Button> {
+    match name {
+        "MouseLeft" => Some(Button::Mouse(MouseButton::Left)),
+        "MouseRight" => Some(Button::Mouse(MouseButton::Right)),
+        "MouseMiddle" => Some(Button::Mouse(MouseButton::Middle)),
+        _ => key_from_name(name).map(Button::Key)
+    }
+}
+
+pub(crate) fn button_name(button: Button) -> Option<&'static str> {
+    match button {
+        Button::Mouse(MouseButton::Left) => Some("MouseLeft"),
+        Button::Mouse(MouseButton::Right) => Some("MouseRight"),
+        Button::Mouse(MouseButton::Middle) => Some("MouseMiddle"),
+        Button::Mouse(_) => None,
+        Button::Key(key) => key_name(key)
+    }
+}
+
+
+// winit 0.28 still requires the deprecated modifiers fields when building events
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use winit::event::{DeviceId, KeyboardInput, ModifiersState};
+    use winit::dpi::PhysicalPosition;
+
+    // DeviceId::dummy() is only unsafe to hand back to winit, these never are
+    fn key_event(key: VirtualKeyCode, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty()
+            },
+            is_synthetic: false
+        }
+    }
+
+    fn cursor_event(x: f64, y: f64) -> WindowEvent<'static> {
+        WindowEvent::CursorMoved {
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(x, y),
+            modifiers: ModifiersState::empty()
+        }
+    }
+
+    #[test]
+    fn pressed_held_released() {
+        let mut input = Input::default();
+        let space = Button::Key(VirtualKeyCode::Space);
+
+        input.handle_event(&key_event(VirtualKeyCode::Space, ElementState::Pressed));
+        assert!(input.was_pressed(space));
+        assert!(input.is_held(space));
+
+        // Key repeat, still held but not pressed again
+        input.end_frame();
+        input.handle_event(&key_event(VirtualKeyCode::Space, ElementState::Pressed));
+        assert!(!input.was_pressed(space));
+        assert!(input.is_held(space));
+
+        input.end_frame();
+        input.handle_event(&key_event(VirtualKeyCode::Space, ElementState::Released));
+        assert!(input.was_released(space));
+        assert!(!input.is_held(space));
+
+        input.end_frame();
+        assert!(!input.was_released(space));
+    }
+
+    #[test]
+    fn mouse_delta_and_wheel() {
+        let mut input = Input::default();
+
+        // The first position has nothing to compare to
+        input.handle_event(&cursor_event(10.0, 10.0));
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+
+        input.handle_event(&cursor_event(15.0, 8.0));
+        input.handle_event(&cursor_event(20.0, 9.0));
+        assert_eq!(input.mouse_delta(), (10.0, -1.0));
+        assert_eq!(input.mouse_position(), Some((20.0, 9.0)));
+
+        input.handle_event(&WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(0.0, 2.0),
+            phase: winit::event::TouchPhase::Moved,
+            modifiers: ModifiersState::empty()
+        });
+        assert_eq!(input.wheel_delta(), 2.0);
+
+        input.end_frame();
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+        assert_eq!(input.wheel_delta(), 0.0);
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input = Input::default();
+        input.handle_event(&key_event(VirtualKeyCode::W, ElementState::Pressed));
+        input.handle_event(&WindowEvent::Focused(false));
+
+        assert!(!input.is_held(Button::Key(VirtualKeyCode::W)));
+        assert!(input.was_released(Button::Key(VirtualKeyCode::W)));
+    }
+
+    #[test]
+    fn actions_and_axes_from_text() {
+        let map = InputMap::parse("
+            # Comments and blank lines are skipped
+            action jump Space
+            action fire MouseLeft
+            action fire LControl
+
+            axis move_x D A
+            axis move_x Right Left
+            axis look_x MouseX
+        ").unwrap();
+
+        let mut input = Input::new(map);
+        input.handle_event(&key_event(VirtualKeyCode::LControl, ElementState::Pressed));
+        input.handle_event(&key_event(VirtualKeyCode::D, ElementState::Pressed));
+        input.handle_event(&key_event(VirtualKeyCode::Right, ElementState::Pressed));
+
+        assert!(input.action_pressed("fire"));
+        assert!(input.action_held("fire"));
+        assert!(!input.action_held("jump"));
+        assert!(!input.action_held("not bound"));
+        assert_eq!(input.axis("move_x"), 2.0);
+
+        input.handle_event(&key_event(VirtualKeyCode::A, ElementState::Pressed));
+        assert_eq!(input.axis("move_x"), 1.0);
+
+        input.handle_event(&cursor_event(0.0, 0.0));
+        input.handle_event(&cursor_event(-3.0, 0.0));
+        assert_eq!(input.axis("look_x"), -3.0);
+        assert_eq!(input.axis("not bound"), 0.0);
+    }
+
+    #[test]
+    fn button_names_round_trip() {
+        for name in ["A", "Key7", "F12", "LShift", "Space", "MouseRight"] {
+            assert_eq!(button_name(parse_button(name).unwrap()), Some(name));
+        }
+        assert_eq!(button_name(Button::Key(VirtualKeyCode::Numpad1)), None);
+    }
+
+    #[test]
+    fn input_map_errors() {
+        let error = InputMap::parse("action jump\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Expected a name and a button");
+
+        let error = InputMap::parse("action jump Space\naxis move_x D Nope\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Unknown button 'Nope'");
+
+        let error = InputMap::parse("bind jump Space\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Unknown statement 'bind'");
+    }
+}
@@ -1,9 +1,19 @@
+// Lets derive macros refer to ::lib_engine paths from inside this crate too
+extern crate self as lib_engine;
+
+pub mod app;
 pub mod transform;
 pub mod math_utils;
 pub mod object;
 pub mod component;
 pub mod scene;
 pub mod renderer;
+pub mod controller;
+pub mod input;
+pub mod recording;
+pub mod text;
+pub mod overlay;
+pub mod debug_draw;
 
 #[cfg(test)]
 pub mod test_utils {
@@ -1,9 +1,12 @@
+pub mod vector2d;
 pub mod vector3d;
+pub mod vector4d;
 pub mod matrix4x4;
 pub mod quaternion;
+pub mod ray;
 
 pub fn clamp<T: PartialOrd>(val: T, min: T, max: T) -> T {
-    if !(min <= max) { panic!("min bigger than max")}
+    if min > max { panic!("min bigger than max")}
 
     if val > max {
         return max;
@@ -1,117 +1,291 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::ops::{Index, IndexMut, Mul, MulAssign};
+
 use super::vector3d::Vector3D;
+use super::vector4d::Vector4D;
+use super::quaternion::Quaternion;
 
+// Below this, a pivot is treated as zero while inverting
+const EPSILON: f64 = 1e-12;
 
-pub type Matrix4x4 = [[f64; 4]; 4];
+// Row-major, and meant to be used with row vectors: v * M
+// So translation lives in the 4th row, and M1 * M2 applies M1 first
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Matrix4x4([[f64; 4]; 4]);
 
-pub const IDENTITY_MATRIX4X4: Matrix4x4 = [
-    [1.0, 0.0, 0.0, 0.0], 
-    [0.0, 1.0, 0.0, 0.0], 
-    [0.0, 0.0, 1.0, 0.0], 
-    [0.0, 0.0, 0.0, 1.0]
-];
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MatrixError {
+    Singular
+}
 
-pub fn invert_matrix(matrix: &Matrix4x4, ignore_4th_col: bool) -> Result<Matrix4x4, String> {
-    let row: usize = 4;
-    let col: usize = if ignore_4th_col { 3 } else { 4 };
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Singular => write!(f, "Matrix has no inverse")
+        }
+    }
+}
 
-    let mut matrix = matrix.clone();
-    let mut inv_matrix = IDENTITY_MATRIX4X4.clone();
+impl std::error::Error for MatrixError {}
 
-    for column in 0..col {
-        // Making sure pivot is a non-zero number
-        // If zero, swap row with one that has the biggest absolute value
-        let mut pivot = column;
-        let mut pivot_val = matrix[column][column];
+impl Default for Matrix4x4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
 
-        if pivot_val == 0.0 {
-            for curr_row in 0..row {
-                if matrix[curr_row][column].abs() > pivot_val.abs() {
-                    pivot = curr_row;
-                    pivot_val = matrix[curr_row][column];
-                }
-            }
+impl Matrix4x4 {
+    pub const IDENTITY: Self = Self([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0]
+    ]);
+
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self(rows)
+    }
 
-            if pivot_val == 0.0 { return Err("Matrix has no inverse".to_string()) }
-            let mut tmp = matrix[pivot];
-            matrix[pivot] = matrix[column];
-            matrix[column] = tmp;
+    pub fn rows(&self) -> [[f64; 4]; 4] {
+        self.0
+    }
 
-            tmp = inv_matrix[pivot];
-            inv_matrix[pivot] = inv_matrix[column];
-            inv_matrix[column] = tmp;
-        }
+    pub fn translation(offset: Vector3D) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix[3] = [offset.x, offset.y, offset.z, 1.0];
+        matrix
+    }
+
+    pub fn scale(scale: Vector3D) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix[0][0] = scale.x;
+        matrix[1][1] = scale.y;
+        matrix[2][2] = scale.z;
+        matrix
+    }
+
+    pub fn rotation(rotation: Quaternion) -> Self {
+        let mut matrix = Self::IDENTITY;
+        rotation.update_3x4_matrix(&mut matrix, &Vector3D::new(1, 1, 1));
+        matrix
+    }
+
+    // Scaled, then rotated, then moved, which is the order Transform uses
+    pub fn from_trs(position: Vector3D, rotation: Quaternion, scale: Vector3D) -> Self {
+        let mut matrix = Self::IDENTITY;
+        rotation.update_3x4_matrix(&mut matrix, &scale);
+        matrix[3] = [position.x, position.y, position.z, 1.0];
+        matrix
+    }
 
-        // Forward substitution
-        for row_under_pivot in (column + 1)..row {
-            // Refer to scratchapixel.com, under Gauss-Jordan Matrix Inverse
-            // cuz I dunno wth I'm doing
-            let multiplier = matrix[row_under_pivot][column] / pivot_val;
-            for i in 0..col {
-                matrix[row_under_pivot][i] -= multiplier * matrix[column][i];
-                inv_matrix[row_under_pivot][i] -= multiplier * inv_matrix[column][i];
+    // Inverse of from_trs() without any elimination:
+    // (S R T)^-1 = T^-1 R^T S^-1, since a rotation's inverse is its transpose
+    // A zero scale has no inverse, and will give infinities instead of an error
+    pub fn inverse_trs(position: Vector3D, rotation: Quaternion, scale: Vector3D) -> Self {
+        let rotation = Self::rotation(rotation).transpose();
+        let reciprocal_scale = [1.0 / scale.x, 1.0 / scale.y, 1.0 / scale.z];
+
+        let mut result = Self::IDENTITY;
+        for (row, rotation_row) in result.0.iter_mut().zip(rotation.0).take(3) {
+            for column in 0..3 {
+                row[column] = rotation_row[column] * reciprocal_scale[column];
             }
+        }
+
+        let translation = result.transform_direction(-position);
+        result[3] = [translation.x, translation.y, translation.z, 1.0];
+        result
+    }
+
+    // World to camera space for a camera at eye, looking at target
+    // The camera looks down its +Z, same as Camera
+    pub fn look_at(eye: Vector3D, target: Vector3D, up: Vector3D) -> Self {
+        let forward = (target - eye).normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+
+        Self([
+            [right.x, up.x, forward.x, 0.0],
+            [right.y, up.y, forward.y, 0.0],
+            [right.z, up.z, forward.z, 0.0],
+            [-right.dot(eye), -up.dot(eye), -forward.dot(eye), 1.0]
+        ])
+    }
+
+    // fov is horizontal and in degrees, aspect ratio is width over height,
+    // and depth goes from 0 at the near plane to 1 at the far plane after dividing by w = -z
+    // Dividing by -z flips both axes, screen Y goes down so that one's wanted,
+    // but X is flipped back so +X stays on the right
+    pub fn perspective(fov: f64, aspect_ratio: f64, near: f64, far: f64) -> Self {
+        let fov_tan_val = (fov/2.0 * PI/180.0).tan();
+        let near_far_interval = far - near;
+
+        Self([
+            [-1.0 / fov_tan_val, 0.0, 0.0, 0.0],
+            [0.0, aspect_ratio / fov_tan_val, 0.0, 0.0],
+            [0.0, 0.0, -far / near_far_interval, -1.0],
+            [0.0, 0.0, far*near / near_far_interval, 0.0]
+        ])
+    }
+
+    // w stays 1, so the Y flip the perspective divide would do
+    // is baked into a negative scaler instead
+    pub fn orthographic(width: f64, height: f64, near: f64, far: f64) -> Self {
+        let near_far_interval = far - near;
+
+        Self([
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, -2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / near_far_interval, 0.0],
+            [0.0, 0.0, -near / near_far_interval, 1.0]
+        ])
+    }
 
-            matrix[row_under_pivot][column] = 0.0;
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::IDENTITY;
+        for (i, row) in self.0.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                result[j][i] = *value;
+            }
         }
+
+        result
+    }
+
+    // Laplace expansion along the 1st row
+    pub fn determinant(&self) -> f64 {
+        (0..4)
+            .map(|column| {
+                let sign = if column % 2 == 0 { 1.0 } else { -1.0 };
+                sign * self.0[0][column] * self.minor(0, column)
+            })
+            .sum()
     }
 
-    // Divide each row to turn the pivot into 1
-    for column in 0..col {
-        let divisor = matrix[column][column];
-        for i in 0..col {
-            matrix[column][i] /= divisor;
-            inv_matrix[column][i] /= divisor;
+    // Determinant of the 3x3 matrix left after removing a row and a column
+    fn minor(&self, row: usize, column: usize) -> f64 {
+        let mut sub = [[0.0; 3]; 3];
+        let rows = (0..4).filter(|&i| i != row);
+
+        for (sub_row, i) in sub.iter_mut().zip(rows) {
+            let columns = (0..4).filter(|&j| j != column);
+            for (value, j) in sub_row.iter_mut().zip(columns) {
+                *value = self.0[i][j];
+            }
         }
-        matrix[column][column] = 1.0;
+
+        sub[0][0] * (sub[1][1]*sub[2][2] - sub[1][2]*sub[2][1])
+        - sub[0][1] * (sub[1][0]*sub[2][2] - sub[1][2]*sub[2][0])
+        + sub[0][2] * (sub[1][0]*sub[2][1] - sub[1][1]*sub[2][0])
     }
 
-    // Backward substitution
-    for row in 0..row {
-        for column in (row+1)..col {
-            let constant = matrix[row][column];
-            for i in 0..col {
-                matrix[row][i] -= matrix[column][i] * constant;
-                inv_matrix[row][i] -= inv_matrix[column][i] * constant;
+    // Gauss-Jordan elimination, picking the biggest pivot in each column
+    // Refer to scratchapixel.com, under Gauss-Jordan Matrix Inverse
+    #[allow(clippy::needless_range_loop)]
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        let mut matrix = self.0;
+        let mut inv_matrix = Self::IDENTITY.0;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+                .unwrap();
+
+            if matrix[pivot][column].abs() < EPSILON { return Err(MatrixError::Singular) }
+            matrix.swap(pivot, column);
+            inv_matrix.swap(pivot, column);
+
+            // Turn the pivot into 1
+            let divisor = matrix[column][column];
+            for i in 0..4 {
+                matrix[column][i] /= divisor;
+                inv_matrix[column][i] /= divisor;
             }
 
-            matrix[row][column] = 0.0;
+            // Clear the rest of the column, above and below the pivot
+            for row in 0..4 {
+                if row == column { continue }
+
+                let multiplier = matrix[row][column];
+                for i in 0..4 {
+                    matrix[row][i] -= multiplier * matrix[column][i];
+                    inv_matrix[row][i] -= multiplier * inv_matrix[column][i];
+                }
+            }
         }
+
+        Ok(Self(inv_matrix))
+    }
+
+    // w = 1, divided away at the end, so projections work too
+    pub fn transform_point(&self, point: Vector3D) -> Vector3D {
+        (Vector4D::from_point(point) * *self).perspective_divide()
+    }
+
+    // w = 0, so translation is skipped
+    pub fn transform_direction(&self, direction: Vector3D) -> Vector3D {
+        (Vector4D::from_direction(direction) * *self).xyz()
     }
-    Ok(inv_matrix)
 }
 
-pub fn vector_matrix_multiply(matrix: &Matrix4x4, vector: Vector3D, non_homogeneous: bool) -> Vector3D {
-    let x = matrix[0][0]*vector.x + matrix[1][0]*vector.y + matrix[2][0]*vector.z + matrix[3][0];
-    let y = matrix[0][1]*vector.x + matrix[1][1]*vector.y + matrix[2][1]*vector.z + matrix[3][1];
-    let z = matrix[0][2]*vector.x + matrix[1][2]*vector.y + matrix[2][2]*vector.z + matrix[3][2];
+impl From<[[f64; 4]; 4]> for Matrix4x4 {
+    fn from(rows: [[f64; 4]; 4]) -> Self {
+        Self(rows)
+    }
+}
 
-    if non_homogeneous {
-        return Vector3D::new(x, y, z)
+impl Index<usize> for Matrix4x4 {
+    type Output = [f64; 4];
+
+    fn index(&self, row: usize) -> &Self::Output {
+        &self.0[row]
     }
+}
 
-    let w = matrix[0][3]*vector.x + matrix[1][3]*vector.y + matrix[2][3]*vector.z + matrix[3][3];
-    Vector3D {
-        x: x/w,
-        y: y/w,
-        z: z/w
+impl IndexMut<usize> for Matrix4x4 {
+    fn index_mut(&mut self, row: usize) -> &mut Self::Output {
+        &mut self.0[row]
     }
 }
 
-pub fn matrix_multiply(matrix1: &Matrix4x4, matrix2: &Matrix4x4) -> Matrix4x4 {
-    let mut result = IDENTITY_MATRIX4X4.clone();
-    const SIZE: usize = 4;
 
-    for x in 0..SIZE {
-        for y in 0..SIZE {
-            let mut sum = 0.0;
-            for num in 0..4 {
-                sum += matrix1[y][num] * matrix2[num][x];
-                result[y][x] = sum;
+impl Mul for Matrix4x4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut result = Self([[0.0; 4]; 4]);
+
+        for row in 0..4 {
+            for column in 0..4 {
+                result[row][column] = (0..4)
+                    .map(|i| self[row][i] * rhs[i][column])
+                    .sum();
             }
         }
+
+        result
     }
+}
 
-    result
+impl MulAssign for Matrix4x4 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+// Row vector times matrix, w is kept as is
+impl Mul<Matrix4x4> for Vector4D {
+    type Output = Vector4D;
+
+    fn mul(self, rhs: Matrix4x4) -> Self::Output {
+        Vector4D {
+            x: rhs[0][0]*self.x + rhs[1][0]*self.y + rhs[2][0]*self.z + rhs[3][0]*self.w,
+            y: rhs[0][1]*self.x + rhs[1][1]*self.y + rhs[2][1]*self.z + rhs[3][1]*self.w,
+            z: rhs[0][2]*self.x + rhs[1][2]*self.y + rhs[2][2]*self.z + rhs[3][2]*self.w,
+            w: rhs[0][3]*self.x + rhs[1][3]*self.y + rhs[2][3]*self.z + rhs[3][3]*self.w
+        }
+    }
 }
 
 
@@ -119,23 +293,32 @@ pub fn matrix_multiply(matrix1: &Matrix4x4, matrix2: &Matrix4x4) -> Matrix4x4 {
 mod tests {
     use super::*;
     use crate::test_utils::round_place;
+    use rand::Rng;
 
     fn compare_matrices(mat1: &Matrix4x4, mat2: &Matrix4x4, precision: usize) {
-        let flat_rounded_mat1 = mat1.iter().flatten().map(|x| round_place(*x, precision));
-        let flat_rounded_mat2 = mat2.iter().flatten().map(|x| round_place(*x, precision));
+        let flat_rounded_mat1 = mat1.0.iter().flatten().map(|x| round_place(*x, precision));
+        let flat_rounded_mat2 = mat2.0.iter().flatten().map(|x| round_place(*x, precision));
 
         assert!(flat_rounded_mat1.eq(flat_rounded_mat2));
     }
 
+    fn round_vector3d(vector: Vector3D, precision: usize) -> Vector3D {
+        Vector3D::new(
+            round_place(vector.x, precision),
+            round_place(vector.y, precision),
+            round_place(vector.z, precision)
+        )
+    }
+
     #[test]
     fn invert_trs_matrix() {
-        let mat = [
+        let mat = Matrix4x4::new([
             [1.0, 0.0, 0.0, 0.0],
             [0.0, 1.0, 0.0, 0.0],
             [0.0, 0.0, 1.0, 0.0],
             [3.0, 10.0, 1.0, 1.0]
-        ];
-        let result = invert_matrix(&mat, true).unwrap();
+        ]);
+        let result = mat.inverse().unwrap();
         assert_eq!(round_place(result[3][0], 2), -3.0);
         assert_eq!(round_place(result[3][1], 2), -10.0);
         assert_eq!(round_place(result[3][2], 2), -1.0);
@@ -143,93 +326,211 @@ mod tests {
 
     #[test]
     fn invert_whole_matrix() {
-        let matrix: Matrix4x4 = [
+        let matrix = Matrix4x4::new([
             [1.0, 0.5, 3.0, 0.0],
             [5.0, 6.0, 0.6, 0.0],
             [3.0, 1.0, 11.0, -12.0],
             [13.0, 14.0, 15.0, 2.0]
-        ];
+        ]);
 
-        let expected_result = [
+        let expected_result = Matrix4x4::new([
             [3.85953, 1.42211, -0.12309, -0.73853],
             [-3.17385, -0.98748, 0.10014, 0.60083],
             [-0.4242, -0.30946, 0.02434, 0.14604],
             [0.31154, -0.01043, -0.08345, -0.0007]
-        ];
+        ]);
 
-        let result = invert_matrix(&matrix, false).unwrap();
+        let result = matrix.inverse().unwrap();
         compare_matrices(&expected_result, &result, 5);
+        compare_matrices(&(matrix * result), &Matrix4x4::IDENTITY, 9);
+    }
+
+    #[test]
+    fn invert_singular_matrix() {
+        let matrix = Matrix4x4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 6.0, 8.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]);
+
+        assert_eq!(matrix.inverse(), Err(MatrixError::Singular));
+        assert_eq!(matrix.determinant(), 0.0);
+    }
+
+    #[test]
+    fn determinant_and_transpose() {
+        let matrix = Matrix4x4::new([
+            [1.0, 0.5, 3.0, 0.0],
+            [5.0, 6.0, 0.6, 0.0],
+            [3.0, 1.0, 11.0, -12.0],
+            [13.0, 14.0, 15.0, 2.0]
+        ]);
+
+        assert_eq!(round_place(matrix.determinant(), 5), 287.6);
+        assert_eq!(matrix.transpose()[3], [0.0, 0.0, -12.0, 2.0]);
+        assert_eq!(matrix.transpose().transpose(), matrix);
+        assert_eq!(Matrix4x4::scale(Vector3D::new(2, 3, 4)).determinant(), 24.0);
     }
 
     #[test]
     fn vector_multiply_normal() {
-        let matrix = [
+        let matrix = Matrix4x4::new([
             [1.0, 2.0, 3.0, 0.0],
             [4.0, 5.0, 6.0, 0.0],
             [7.0, 8.0, 9.0, 0.0],
             [10.0, 11.0, 12.0, 1.0],
-        ];
+        ]);
         let vector = Vector3D::new(1,2,3);
 
-        let result = vector_matrix_multiply(&matrix, vector, true);
+        let result = matrix.transform_point(vector);
         assert_eq!(result, Vector3D::new(40, 47, 54));
     }
 
     #[test]
     fn vector_multiply_homogeneous() {
-        let matrix = [
+        let matrix = Matrix4x4::new([
             [1.0, 2.0, 3.0, 2.0],
             [4.0, 5.0, 6.0, 5.0],
             [7.0, 8.0, 9.0, 9.0],
             [10.0, 11.0, 12.0, 1.0],
-        ];
+        ]);
         let vector = Vector3D::new(1,2,3);
 
-        let result = vector_matrix_multiply(&matrix, vector, false);
+        let result = matrix.transform_point(vector);
         assert_eq!(result, Vector3D::new(1.0, 1.175, 1.35));
     }
 
+    #[test]
+    fn vector4d_multiply_keeps_w() {
+        let matrix = Matrix4x4::new([
+            [1.0, 2.0, 3.0, 2.0],
+            [4.0, 5.0, 6.0, 5.0],
+            [7.0, 8.0, 9.0, 9.0],
+            [10.0, 11.0, 12.0, 1.0],
+        ]);
+
+        let point = Vector4D::new(1, 2, 3, 1) * matrix;
+        assert_eq!(point, Vector4D::new(40, 47, 54, 40));
+        assert_eq!(point.perspective_divide(), matrix.transform_point(Vector3D::new(1, 2, 3)));
+
+        // Directions skip the translation row
+        let direction = Vector4D::new(1, 2, 3, 0) * matrix;
+        assert_eq!(direction, Vector4D::new(30, 36, 42, 39));
+        assert_eq!(matrix.transform_direction(Vector3D::new(1, 2, 3)), Vector3D::new(30, 36, 42));
+    }
+
     #[test]
     fn matrix_multiply_trs() {
-        let matrix = [
+        let matrix = Matrix4x4::new([
             [1.0, 2.0, 3.0, 0.0],
             [4.0, 5.0, 6.0, 0.0],
             [7.0, 8.0, 9.0, 0.0],
             [10.0, 11.0, 12.0, 1.0],
-        ];
+        ]);
 
-        let result = matrix_multiply(&matrix, &matrix);
+        let result = matrix * matrix;
 
-        let expected_result = [
+        let expected_result = Matrix4x4::new([
             [30.0, 36.0, 42.0, 0.0],
             [66.0, 81.0, 96.0, 0.0],
             [102.0, 126.0, 150.0, 0.0],
             [148.0, 182.0, 216.0, 1.0],
-        ];
-
-        dbg!("{:?}", result);
+        ]);
 
         compare_matrices(&result, &expected_result, 1);
     }
 
     #[test]
     fn matrix_multiply_whole() {
-        let matrix = [
+        let mut matrix = Matrix4x4::new([
             [1.0, 2.0, 3.0, 5.0],
             [4.0, 5.0, 6.0, 0.0],
             [7.0, 8.0, 9.0, 3.0],
             [10.0, 11.0, 12.0, 1.0],
-        ];
+        ]);
 
-        let result = matrix_multiply(&matrix, &matrix);
+        matrix *= matrix;
 
-        let expected_result = [
+        let expected_result = Matrix4x4::new([
             [80.0, 91.0, 102.0, 19.0],
             [66.0, 81.0, 96.0, 38.0],
             [132.0, 159.0, 186.0, 65.0],
             [148.0, 182.0, 216.0, 87.0],
-        ];
+        ]);
 
-        compare_matrices(&result, &expected_result, 1);
+        compare_matrices(&matrix, &expected_result, 1);
+    }
+
+    #[test]
+    fn compose_constructors() {
+        let scale = Matrix4x4::scale(Vector3D::new(2, 2, 2));
+        let rotation = Matrix4x4::rotation(Quaternion::from_euler_angles(0.0, 0.0, PI / 2.0));
+        let translation = Matrix4x4::translation(Vector3D::new(10, 0, 0));
+
+        // Scaled, then rotated, then moved
+        let matrix = scale * rotation * translation;
+        let result = round_vector3d(matrix.transform_point(Vector3D::new(1, 0, 0)), 9);
+        assert_eq!(result, Vector3D::new(10, -2, 0));
+
+        let direction = round_vector3d(translation.transform_direction(Vector3D::new(0, 1, 0)), 9);
+        assert_eq!(direction, Vector3D::new(0, 1, 0));
+    }
+
+    #[test]
+    fn inverse_trs_matches_general_inverse() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let position = Vector3D::new(
+                rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)
+            );
+            let rotation = Quaternion::from_euler_angles(
+                rng.gen_range(-PI..PI), rng.gen_range(-PI..PI), rng.gen_range(-PI..PI)
+            );
+            let scale = Vector3D::new(
+                rng.gen_range(0.1..10.0), rng.gen_range(0.1..10.0), rng.gen_range(0.1..10.0)
+            );
+
+            let matrix = Matrix4x4::from_trs(position, rotation, scale);
+            let fast = Matrix4x4::inverse_trs(position, rotation, scale);
+
+            compare_matrices(&fast, &matrix.inverse().unwrap(), 6);
+            compare_matrices(&(matrix * fast), &Matrix4x4::IDENTITY, 6);
+        }
+    }
+
+    #[test]
+    fn from_trs_non_uniform_scale() {
+        let rotation = Quaternion::from_euler_angles(0.0, 0.0, PI / 2.0);
+        let matrix = Matrix4x4::from_trs(Vector3D::new(0, 0, 0), rotation, Vector3D::new(2, 1, 1));
+
+        let expected = Matrix4x4::scale(Vector3D::new(2, 1, 1)) * Matrix4x4::rotation(rotation);
+        compare_matrices(&matrix, &expected, 9);
+    }
+
+    #[test]
+    fn look_at_view_matrix() {
+        let eye = Vector3D::new(0, 0, -5);
+        let view = Matrix4x4::look_at(eye, Vector3D::new(0, 0, 0), Vector3D::new(0, 1, 0));
+
+        assert_eq!(view.transform_point(eye), Vector3D::new(0, 0, 0));
+        assert_eq!(view.transform_point(Vector3D::new(1, 2, 0)), Vector3D::new(1, 2, 5));
+
+        // Looking down -X from the side, world +Z ends up on the camera's right
+        let view = Matrix4x4::look_at(Vector3D::new(5, 0, 0), Vector3D::new(0, 0, 0), Vector3D::new(0, 1, 0));
+        let result = round_vector3d(view.transform_point(Vector3D::new(0, 0, 1)), 9);
+        assert_eq!(result, Vector3D::new(1, 0, 5));
+    }
+
+    #[test]
+    fn projection_depth_range() {
+        let perspective = Matrix4x4::perspective(90.0, 16.0 / 9.0, 1.0, 30.0);
+        assert_eq!(perspective.transform_point(Vector3D::new(0, 0, 1)).z, 0.0);
+        assert_eq!(round_place(perspective.transform_point(Vector3D::new(0, 0, 30)).z, 9), 1.0);
+
+        let orthographic = Matrix4x4::orthographic(16.0, 9.0, 1.0, 11.0);
+        assert_eq!(orthographic.transform_point(Vector3D::new(4.0, 2.25, 1.0)), Vector3D::new(0.5, -0.5, 0.0));
+        assert_eq!(orthographic.transform_point(Vector3D::new(4.0, 2.25, 11.0)).z, 1.0);
     }
 }
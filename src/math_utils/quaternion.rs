@@ -2,9 +2,15 @@ use crate::math_utils::{matrix4x4, vector3d};
 use matrix4x4::Matrix4x4;
 use vector3d::Vector3D;
 
+use std::f64::consts::FRAC_PI_2;
 use std::ops::{Mul, MulAssign};
 
+// Past this dot product, slerp falls back to nlerp to avoid dividing by ~0
+const SLERP_THRESHOLD: f64 = 0.9995;
+
 // Reminder: Quaternion(w,x,y,z)
+// Rotations are left-handed like the rest of the engine: looking down an axis,
+// a positive angle turns clockwise. q * r rotates by q first, then r
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Quaternion(f64, f64, f64, f64);
 pub const IDENTITY_QUATERNION: Quaternion = Quaternion(1.0, 0.0, 0.0, 0.0);
@@ -31,6 +37,15 @@ impl MulAssign for Quaternion {
 }
 
 impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion(w, x, y, z)
+    }
+
+    // In (w,x,y,z) order, same as the tuple
+    pub fn components(&self) -> [f64; 4] {
+        [self.0, self.1, self.2, self.3]
+    }
+
     pub fn from_euler_angles<T: Into<f64>>(x: T, y: T, z: T) -> Self
     {
         // Got this abomination from Wikipedia lul
@@ -55,6 +70,156 @@ impl Quaternion {
         )
     }
 
+    // Axis doesn't need to be normalized, angle is in radians
+    pub fn from_axis_angle(axis: Vector3D, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+
+        Quaternion(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    // Only the upper 3x3 is read, and it's expected to be a pure rotation (no scale)
+    // Shepperd's method, picking whichever component is biggest to stay stable
+    // https://www.euclideanspace.com/maths/geometry/rotations/conversions/matrixToQuaternion/
+    pub fn from_rotation_matrix(matrix: &Matrix4x4) -> Self {
+        let m = matrix;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion(0.25 * s, (m[2][1] - m[1][2]) / s, (m[0][2] - m[2][0]) / s, (m[1][0] - m[0][1]) / s)
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Quaternion((m[2][1] - m[1][2]) / s, 0.25 * s, (m[0][1] + m[1][0]) / s, (m[0][2] + m[2][0]) / s)
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Quaternion((m[0][2] - m[2][0]) / s, (m[0][1] + m[1][0]) / s, 0.25 * s, (m[1][2] + m[2][1]) / s)
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Quaternion((m[1][0] - m[0][1]) / s, (m[0][2] + m[2][0]) / s, (m[1][2] + m[2][1]) / s, 0.25 * s)
+        };
+
+        q.normalize()
+    }
+
+    // Rotation that turns +Z towards forward, with +Y as close to up as possible
+    pub fn look_rotation(forward: Vector3D, up: Vector3D) -> Self {
+        let forward = forward.normalize();
+        let mut right = up.cross(forward);
+
+        // Looking straight along up, any right will do
+        if right.length_squared() < 1e-12 {
+            let fallback_up = if forward.z.abs() < 0.9 { Vector3D::new(0, 0, 1) } else { Vector3D::new(1, 0, 0) };
+            right = fallback_up.cross(forward);
+        }
+
+        let right = right.normalize();
+        let up = forward.cross(right);
+
+        // Each row is where the matching axis ends up
+        let matrix = Matrix4x4::new([
+            [right.x, right.y, right.z, 0.0],
+            [up.x, up.y, up.z, 0.0],
+            [forward.x, forward.y, forward.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]);
+
+        Self::from_rotation_matrix(&matrix)
+    }
+
+    // Inverse of from_euler_angles(), in radians
+    // Y is clamped to +-90 degrees, past that X and Z flip to compensate
+    pub fn to_euler_angles(&self) -> Vector3D {
+        let Quaternion(w, x, y, z) = *self;
+
+        let x_angle = (2.0 * (w*x + y*z)).atan2(1.0 - 2.0 * (x*x + y*y));
+        let sin_y = 2.0 * (w*y - x*z);
+        let y_angle = if sin_y.abs() >= 1.0 { FRAC_PI_2.copysign(sin_y) } else { sin_y.asin() };
+        let z_angle = (2.0 * (w*z + x*y)).atan2(1.0 - 2.0 * (y*y + z*z));
+
+        Vector3D::new(x_angle, y_angle, z_angle)
+    }
+
+    pub fn to_matrix(&self) -> Matrix4x4 {
+        Matrix4x4::rotation(*self)
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Quaternion(self.0, -self.1, -self.2, -self.3)
+    }
+
+    // Same as the conjugate for unit quaternions,
+    // a zero quaternion has no inverse and gives NaNs
+    pub fn inverse(&self) -> Self {
+        let Quaternion(w, x, y, z) = self.conjugate();
+        let magnitude_sq = self.dot(*self);
+
+        Quaternion(w / magnitude_sq, x / magnitude_sq, y / magnitude_sq, z / magnitude_sq)
+    }
+
+    pub fn dot(&self, rhs: Self) -> f64 {
+        self.0*rhs.0 + self.1*rhs.1 + self.2*rhs.2 + self.3*rhs.3
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    // Always normalizes, unlike lazy_normalize()
+    // Zero stays zero instead of turning into NaN
+    pub fn normalize(&self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude == 0.0 { return *self }
+
+        Quaternion(self.0 / magnitude, self.1 / magnitude, self.2 / magnitude, self.3 / magnitude)
+    }
+
+    // Same result as multiplying by to_matrix(), without building the matrix
+    pub fn rotate_vector(&self, vector: Vector3D) -> Vector3D {
+        // The rotation is the conjugate of the usual q * v * q^-1,
+        // which is what makes it left-handed
+        let axis = Vector3D::new(-self.1, -self.2, -self.3);
+        let t = axis.cross(vector) * 2.0;
+
+        vector + t * self.0 + axis.cross(t)
+    }
+
+    // Spherical interpolation, constant angular speed, always takes the short way around
+    pub fn slerp(&self, other: Self, t: f64) -> Self {
+        let mut dot = self.dot(other);
+        let mut other = other;
+        if dot < 0.0 {
+            other = Quaternion(-other.0, -other.1, -other.2, -other.3);
+            dot = -dot;
+        }
+
+        if dot > SLERP_THRESHOLD { return self.nlerp(other, t) }
+
+        let angle = dot.acos();
+        let sin_angle = angle.sin();
+        let a = ((1.0 - t) * angle).sin() / sin_angle;
+        let b = (t * angle).sin() / sin_angle;
+
+        Quaternion(
+            a*self.0 + b*other.0,
+            a*self.1 + b*other.1,
+            a*self.2 + b*other.2,
+            a*self.3 + b*other.3
+        )
+    }
+
+    // Normalized linear interpolation, cheaper than slerp but speeds up in the middle
+    pub fn nlerp(&self, other: Self, t: f64) -> Self {
+        let sign = if self.dot(other) < 0.0 { -1.0 } else { 1.0 };
+
+        Quaternion(
+            self.0 + (sign*other.0 - self.0) * t,
+            self.1 + (sign*other.1 - self.1) * t,
+            self.2 + (sign*other.2 - self.2) * t,
+            self.3 + (sign*other.3 - self.3) * t
+        ).normalize()
+    }
+
     pub fn update_3x4_matrix(&self, matrix: &mut Matrix4x4, scale: &Vector3D) {
         let wx = self.0 * self.1 * 2.0;
         let wy = self.0 * self.2 * 2.0;
@@ -69,14 +234,16 @@ impl Quaternion {
 
         let zz = self.3 * self.3 * 2.0;
 
+        // Each row is an axis of the rotated basis, so scaling it
+        // scales along that axis before the rotation is applied
         matrix[0][0] = (1.0 - yy - zz) * scale.x;
-        matrix[0][1] = xy - wz;
-        matrix[0][2] = xz + wy;
-        matrix[1][0] = xy + wz;
+        matrix[0][1] = (xy - wz) * scale.x;
+        matrix[0][2] = (xz + wy) * scale.x;
+        matrix[1][0] = (xy + wz) * scale.y;
         matrix[1][1] = (1.0 - xx - zz) * scale.y;
-        matrix[1][2] = yz - wx;
-        matrix[2][0] = xz - wy;
-        matrix[2][1] = yz + wx;
+        matrix[1][2] = (yz - wx) * scale.y;
+        matrix[2][0] = (xz - wy) * scale.z;
+        matrix[2][1] = (yz + wx) * scale.z;
         matrix[2][2] = (1.0 - xx - yy) * scale.z;
     }
 
@@ -139,6 +306,126 @@ mod tests {
         compare_quaternions(q, 5, 0.78607, 0.16752, 0.57094, 0.16752);
     }
 
+    fn round_vector3d(vector: Vector3D) -> Vector3D {
+        Vector3D::new(round_place(vector.x, 9), round_place(vector.y, 9), round_place(vector.z, 9))
+    }
+
+    fn same_rotation(a: Quaternion, b: Quaternion) {
+        // q and -q are the same rotation
+        assert_eq!(round_place(a.dot(b).abs(), 9), 1.0);
+    }
+
+    #[test]
+    fn quaternion_axis_angle() {
+        let q = Quaternion::from_axis_angle(Vector3D::new(0, 0, 2), 1.0);
+        same_rotation(q, Quaternion::from_euler_angles(0, 0, 1));
+
+        // Clockwise when looking down +Z
+        let rotated = Quaternion::from_axis_angle(Vector3D::new(0, 0, 1), FRAC_PI_2)
+            .rotate_vector(Vector3D::new(1, 0, 0));
+        assert_eq!(round_vector3d(rotated), Vector3D::new(0, -1, 0));
+    }
+
+    #[test]
+    fn quaternion_rotate_vector_matches_matrix() {
+        let q = Quaternion::from_euler_angles(0.3, -1.1, 2.4);
+        let vector = Vector3D::new(1, -2, 5);
+
+        let with_matrix = q.to_matrix().transform_direction(vector);
+        assert_eq!(round_vector3d(q.rotate_vector(vector)), round_vector3d(with_matrix));
+
+        // Composition order matches the matrices too
+        let r = Quaternion::from_euler_angles(1.0, 0.2, 0.0);
+        let with_matrices = (q.to_matrix() * r.to_matrix()).transform_direction(vector);
+        assert_eq!(round_vector3d((q * r).rotate_vector(vector)), round_vector3d(with_matrices));
+        assert_eq!(round_vector3d(r.rotate_vector(q.rotate_vector(vector))), round_vector3d(with_matrices));
+    }
+
+    #[test]
+    fn quaternion_3x4_matrix_scales_whole_rows() {
+        // Only scaling the diagonal used to skew rotated, scaled objects
+        let q = Quaternion::from_euler_angles(0.3, -1.1, 2.4);
+        let scale = Vector3D::new(2.0, 0.5, 3.0);
+        let mut matrix = Matrix4x4::IDENTITY;
+        q.update_3x4_matrix(&mut matrix, &scale);
+
+        let expected = Matrix4x4::scale(scale) * q.to_matrix();
+        for (row, expected_row) in matrix.rows().iter().zip(expected.rows()) {
+            for (a, b) in row.iter().zip(expected_row) {
+                assert_eq!(round_place(*a, 9), round_place(b, 9));
+            }
+        }
+    }
+
+    #[test]
+    fn quaternion_matrix_round_trip() {
+        // Covers every branch of from_rotation_matrix
+        let rotations = [
+            Quaternion::from_euler_angles(0.3, -1.1, 2.4),
+            Quaternion::from_axis_angle(Vector3D::new(1, 0, 0), 3.0),
+            Quaternion::from_axis_angle(Vector3D::new(0, 1, 0), 3.0),
+            Quaternion::from_axis_angle(Vector3D::new(0, 0, 1), 3.0),
+            IDENTITY_QUATERNION
+        ];
+
+        for q in rotations {
+            same_rotation(Quaternion::from_rotation_matrix(&q.to_matrix()), q);
+        }
+    }
+
+    #[test]
+    fn quaternion_to_euler_angles() {
+        let angles = Quaternion::from_euler_angles(0.3, -1.1, 2.4).to_euler_angles();
+        assert_eq!(round_vector3d(angles), Vector3D::new(0.3, -1.1, 2.4));
+
+        // Gimbal lock
+        let angles = Quaternion::from_euler_angles(0.0, FRAC_PI_2, 0.0).to_euler_angles();
+        assert_eq!(round_place(angles.y, 9), round_place(FRAC_PI_2, 9));
+    }
+
+    #[test]
+    fn quaternion_conjugate_inverse() {
+        let q = Quaternion::from_euler_angles(0.3, -1.1, 2.4);
+        compare_quaternions(q * q.conjugate(), 9, 1.0, 0.0, 0.0, 0.0);
+
+        let scaled = Quaternion(3.0, 0.0, 4.0, 0.0);
+        compare_quaternions(scaled * scaled.inverse(), 9, 1.0, 0.0, 0.0, 0.0);
+        compare_quaternions(scaled.normalize(), 9, 0.6, 0.0, 0.8, 0.0);
+        assert_eq!(Quaternion(0.0, 0.0, 0.0, 0.0).normalize(), Quaternion(0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn quaternion_look_rotation() {
+        let forward = Vector3D::new(1, 0, 1);
+        let q = Quaternion::look_rotation(forward, Vector3D::new(0, 1, 0));
+
+        assert_eq!(round_vector3d(q.rotate_vector(Vector3D::new(0, 0, 1))), round_vector3d(forward.normalize()));
+        assert_eq!(round_vector3d(q.rotate_vector(Vector3D::new(0, 1, 0))), Vector3D::new(0, 1, 0));
+
+        // Looking straight up still gives a valid rotation
+        let up = Quaternion::look_rotation(Vector3D::new(0, 1, 0), Vector3D::new(0, 1, 0));
+        assert_eq!(round_vector3d(up.rotate_vector(Vector3D::new(0, 0, 1))), Vector3D::new(0, 1, 0));
+        assert_eq!(round_place(up.magnitude(), 9), 1.0);
+    }
+
+    #[test]
+    fn quaternion_slerp_nlerp() {
+        let start = IDENTITY_QUATERNION;
+        let end = Quaternion::from_axis_angle(Vector3D::new(0, 1, 0), 2.0);
+
+        same_rotation(start.slerp(end, 0.0), start);
+        same_rotation(start.slerp(end, 1.0), end);
+        same_rotation(start.slerp(end, 0.25), Quaternion::from_axis_angle(Vector3D::new(0, 1, 0), 0.5));
+
+        // nlerp lands on the same axis, just not at a constant speed
+        let half = start.nlerp(end, 0.5);
+        same_rotation(half, Quaternion::from_axis_angle(Vector3D::new(0, 1, 0), 1.0));
+
+        // -end is the same rotation, and shouldn't send slerp the long way around
+        let negated = Quaternion(-end.0, -end.1, -end.2, -end.3);
+        same_rotation(start.slerp(negated, 0.25), start.slerp(end, 0.25));
+    }
+
     #[test]
     fn quaternion_multiply() {
         let q1 = Quaternion(1.0, 0.4, 0.5, 0.6);
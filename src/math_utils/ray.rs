@@ -0,0 +1,156 @@
+use super::vector3d::Vector3D;
+use super::matrix4x4::Matrix4x4;
+
+// Below this, the ray is treated as parallel to the triangle
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vector3D,
+    pub direction: Vector3D
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TriangleHit {
+    pub distance: f64,
+    // Weights of the triangle's 1st, 2nd and 3rd vertex at the hit point
+    pub barycentric: [f64; 3]
+}
+
+impl Ray {
+    // Direction is normalized, so distances along the ray are in world units
+    pub fn new(origin: Vector3D, direction: Vector3D) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize()
+        }
+    }
+
+    pub fn at(&self, distance: f64) -> Vector3D {
+        self.origin + self.direction * distance
+    }
+
+    // The direction is NOT renormalized, so a distance found with
+    // the transformed ray is still valid for the original one
+    pub fn transform(&self, matrix: &Matrix4x4) -> Self {
+        Self {
+            origin: matrix.transform_point(self.origin),
+            direction: matrix.transform_direction(self.direction)
+        }
+    }
+
+    // Möller–Trumbore, hits from both sides of the triangle count
+    // https://www.scratchapixel.com/lessons/3d-basic-rendering/ray-tracing-rendering-a-triangle/moller-trumbore-ray-triangle-intersection.html
+    pub fn intersect_triangle(&self, triangle: [Vector3D; 3]) -> Option<TriangleHit> {
+        let [v0, v1, v2] = triangle;
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < EPSILON { return None }
+
+        let inverse_determinant = 1.0 / determinant;
+        let t = self.origin - v0;
+
+        let u = t.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) { return None }
+
+        let q = t.cross(edge1);
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 { return None }
+
+        let distance = edge2.dot(q) * inverse_determinant;
+        // Triangle is behind the ray origin
+        if distance < 0.0 { return None }
+
+        Some(TriangleHit {
+            distance,
+            barycentric: [1.0 - u - v, u, v]
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::round_place;
+
+    fn triangle() -> [Vector3D; 3] {
+        [
+            Vector3D::new(-1, -1, 5),
+            Vector3D::new(1, -1, 5),
+            Vector3D::new(0, 1, 5)
+        ]
+    }
+
+    #[test]
+    fn new_ray_normalizes_direction() {
+        let ray = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 3, 4));
+        assert_eq!(ray.direction, Vector3D::new(0.0, 0.6, 0.8));
+        assert_eq!(ray.at(5.0), Vector3D::new(0, 3, 4));
+    }
+
+    #[test]
+    fn ray_hits_triangle() {
+        let ray = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 0, 1));
+        let hit = ray.intersect_triangle(triangle()).unwrap();
+
+        assert_eq!(hit.distance, 5.0);
+        assert_eq!(hit.barycentric.iter().sum::<f64>(), 1.0);
+        assert_eq!(hit.barycentric, [0.25, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn ray_hits_triangle_vertex_weights() {
+        let ray = Ray::new(Vector3D::new(1, -1, 0), Vector3D::new(0, 0, 1));
+        let hit = ray.intersect_triangle(triangle()).unwrap();
+
+        let rounded = hit.barycentric.map(|x| round_place(x, 5));
+        assert_eq!(rounded, [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn ray_hits_back_face() {
+        let ray = Ray::new(Vector3D::new(0, 0, 10), Vector3D::new(0, 0, -1));
+        let hit = ray.intersect_triangle(triangle()).unwrap();
+
+        assert_eq!(hit.distance, 5.0);
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        let ray = Ray::new(Vector3D::new(2, 0, 0), Vector3D::new(0, 0, 1));
+        assert_eq!(ray.intersect_triangle(triangle()), None);
+    }
+
+    #[test]
+    fn ray_parallel_to_triangle() {
+        let ray = Ray::new(Vector3D::new(0, 0, 5), Vector3D::new(1, 0, 0));
+        assert_eq!(ray.intersect_triangle(triangle()), None);
+    }
+
+    #[test]
+    fn triangle_behind_ray() {
+        let ray = Ray::new(Vector3D::new(0, 0, 6), Vector3D::new(0, 0, 1));
+        assert_eq!(ray.intersect_triangle(triangle()), None);
+    }
+
+    #[test]
+    fn transformed_ray_keeps_distance() {
+        let ray = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 0, 1));
+        // Scale by 2 and move 3 forward
+        let matrix = Matrix4x4::new([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 3.0, 1.0]
+        ]);
+
+        let transformed = ray.transform(&matrix);
+        assert_eq!(transformed.origin, Vector3D::new(0, 0, 3));
+        assert_eq!(transformed.direction, Vector3D::new(0, 0, 2));
+        assert_eq!(transformed.at(1.0), Vector3D::new(0, 0, 5));
+    }
+}
@@ -0,0 +1,260 @@
+use std::ops::{
+    Add, Sub, Mul, Div, Neg,
+    AddAssign, SubAssign, MulAssign, DivAssign
+};
+
+use super::vector3d::Vector3D;
+
+// Mostly for screen space, where Y goes down
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Vector2D {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vector2D {
+    pub fn new<T: Into<f64>>(x: T, y: T) -> Self {
+        Self {
+            x: x.into(),
+            y: y.into()
+        }
+    }
+
+    pub fn extend(&self, z: f64) -> Vector3D {
+        Vector3D::new(self.x, self.y, z)
+    }
+
+    pub fn dot(&self, rhs: Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    // Z component of the 3D cross product, aka the 2D "cross product"
+    // Same math as the rasterizer's edge function
+    pub fn perp_dot(&self, rhs: Self) -> f64 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    // Zero vectors stay zero instead of turning into NaN
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 { return *self }
+
+        *self / length
+    }
+
+    pub fn distance(&self, other: Self) -> f64 {
+        (*self - other).length()
+    }
+
+    // Component-wise
+    pub fn min(&self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y)
+        }
+    }
+
+    // Component-wise
+    pub fn max(&self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y)
+        }
+    }
+
+    pub fn lerp(&self, other: Self, t: f64) -> Self {
+        *self + (other - *self) * t
+    }
+
+    // Normal is expected to be normalized
+    pub fn reflect(&self, normal: Self) -> Self {
+        *self - normal * (2.0 * self.dot(normal))
+    }
+
+    pub fn project(&self, onto: Self) -> Self {
+        let onto_length_squared = onto.length_squared();
+        if onto_length_squared == 0.0 { return onto }
+
+        onto * (self.dot(onto) / onto_length_squared)
+    }
+}
+
+// Drops z
+impl From<Vector3D> for Vector2D {
+    fn from(vector: Vector3D) -> Self {
+        Self::new(vector.x, vector.y)
+    }
+}
+
+// z = 0
+impl From<Vector2D> for Vector3D {
+    fn from(vector: Vector2D) -> Self {
+        vector.extend(0.0)
+    }
+}
+
+
+impl Add for Vector2D {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y
+        }
+    }
+}
+
+impl Sub for Vector2D {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y
+        }
+    }
+}
+
+impl Neg for Vector2D {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y
+        }
+    }
+}
+
+impl Mul<f64> for Vector2D {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs
+        }
+    }
+}
+
+impl Mul<Vector2D> for f64 {
+    type Output = Vector2D;
+
+    fn mul(self, rhs: Vector2D) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div<f64> for Vector2D {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs
+        }
+    }
+}
+
+impl AddAssign for Vector2D {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vector2D {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f64> for Vector2D {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f64> for Vector2D {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_vector2d() {
+        let a = Vector2D::new(1, 2);
+        let b = Vector2D::new(4, -3);
+
+        assert_eq!(a + b, Vector2D::new(5, -1));
+        assert_eq!(a - b, Vector2D::new(-3, 5));
+        assert_eq!(-a, Vector2D::new(-1, -2));
+        assert_eq!(a * 3.0, Vector2D::new(3, 6));
+        assert_eq!(3.0 * a, Vector2D::new(3, 6));
+        assert_eq!(b / 2.0, Vector2D::new(2.0, -1.5));
+    }
+
+    #[test]
+    fn assign_ops_vector2d() {
+        let mut a = Vector2D::new(1, 2);
+        a += Vector2D::new(1, 1);
+        a -= Vector2D::new(0, 2);
+        a *= 4.0;
+        a /= 2.0;
+
+        assert_eq!(a, Vector2D::new(4, 2));
+    }
+
+    #[test]
+    fn products_vector2d() {
+        let a = Vector2D::new(2, 3);
+        let b = Vector2D::new(4, 5);
+
+        assert_eq!(a.dot(b), 23.0);
+        assert_eq!(a.perp_dot(b), -2.0);
+        assert_eq!(b.perp_dot(a), 2.0);
+    }
+
+    #[test]
+    fn length_vector2d() {
+        let a = Vector2D::new(3, 4);
+
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.normalize(), Vector2D::new(0.6, 0.8));
+        assert_eq!(a.distance(Vector2D::new(0, 0)), 5.0);
+        assert_eq!(Vector2D::new(0, 0).normalize(), Vector2D::new(0, 0));
+    }
+
+    #[test]
+    fn helpers_vector2d() {
+        let a = Vector2D::new(0, 4);
+        let b = Vector2D::new(2, -4);
+
+        assert_eq!(a.min(b), Vector2D::new(0, -4));
+        assert_eq!(a.max(b), Vector2D::new(2, 4));
+        assert_eq!(a.lerp(b, 0.5), Vector2D::new(1, 0));
+        assert_eq!(Vector2D::new(1, -1).reflect(Vector2D::new(0, 1)), Vector2D::new(1, 1));
+        assert_eq!(Vector2D::new(3, 4).project(Vector2D::new(2, 0)), Vector2D::new(3, 0));
+    }
+
+    #[test]
+    fn convert_vector2d() {
+        let flat = Vector2D::from(Vector3D::new(1, 2, 3));
+        assert_eq!(flat, Vector2D::new(1, 2));
+        assert_eq!(flat.extend(5.0), Vector3D::new(1, 2, 5));
+        assert_eq!(Vector3D::from(flat), Vector3D::new(1, 2, 0));
+    }
+}
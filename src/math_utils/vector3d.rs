@@ -1,4 +1,7 @@
-use std::ops::{Add, Sub, Mul};
+use std::ops::{
+    Add, Sub, Mul, Div, Neg,
+    AddAssign, SubAssign, MulAssign, DivAssign
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Vector3D {
@@ -15,6 +18,75 @@ impl Vector3D {
             z: z.into()
         }
     }
+
+    pub fn dot(&self, rhs: Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    // Left-handed coordinates, so X cross Y gives Z (forward)
+    pub fn cross(&self, rhs: Self) -> Self {
+        Self {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x
+        }
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    // Zero vectors stay zero instead of turning into NaN
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 { return *self }
+
+        *self / length
+    }
+
+    pub fn distance(&self, other: Self) -> f64 {
+        (*self - other).length()
+    }
+
+    // Component-wise
+    pub fn min(&self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z)
+        }
+    }
+
+    // Component-wise
+    pub fn max(&self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z)
+        }
+    }
+
+    // t isn't clamped, so values outside 0..1 extrapolate
+    pub fn lerp(&self, other: Self, t: f64) -> Self {
+        *self + (other - *self) * t
+    }
+
+    // Normal is expected to be normalized
+    pub fn reflect(&self, normal: Self) -> Self {
+        *self - normal * (2.0 * self.dot(normal))
+    }
+
+    // Projection of this vector onto another one
+    pub fn project(&self, onto: Self) -> Self {
+        let onto_length_squared = onto.length_squared();
+        if onto_length_squared == 0.0 { return onto }
+
+        onto * (self.dot(onto) / onto_length_squared)
+    }
 }
 
 
@@ -43,12 +115,76 @@ impl Sub for Vector3D {
     }
 }
 
-// Dot product for vector
-impl Mul for Vector3D {
-    type Output = f64;
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+impl Neg for Vector3D {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z
+        }
+    }
+}
+
+
+// Scalar multiplication, use dot() or cross() for vector products
+impl Mul<f64> for Vector3D {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs
+        }
+    }
+}
+
+impl Mul<Vector3D> for f64 {
+    type Output = Vector3D;
+
+    fn mul(self, rhs: Vector3D) -> Self::Output {
+        rhs * self
+    }
+}
+
+
+impl Div<f64> for Vector3D {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs
+        }
+    }
+}
+
+
+impl AddAssign for Vector3D {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vector3D {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f64> for Vector3D {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f64> for Vector3D {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
     }
 }
 
@@ -78,6 +214,98 @@ mod tests {
         let a = Vector3D::new(5,6,7);
         let b = Vector3D::new(10,2,3);
 
-        assert_eq!(a*b, 83_f64);
+        assert_eq!(a.dot(b), 83_f64);
+    }
+
+    #[test]
+    fn cross_product_vector3d() {
+        let x = Vector3D::new(1, 0, 0);
+        let y = Vector3D::new(0, 1, 0);
+
+        assert_eq!(x.cross(y), Vector3D::new(0, 0, 1));
+        assert_eq!(y.cross(x), Vector3D::new(0, 0, -1));
+
+        let a = Vector3D::new(2, 3, 4);
+        let b = Vector3D::new(5, 6, 7);
+        assert_eq!(a.cross(b), Vector3D::new(-3, 6, -3));
+        assert_eq!(a.cross(b).dot(a), 0.0);
+    }
+
+    #[test]
+    fn length_and_normalize() {
+        let a = Vector3D::new(2, 3, 6);
+
+        assert_eq!(a.length_squared(), 49.0);
+        assert_eq!(a.length(), 7.0);
+        assert_eq!(a.normalize(), Vector3D::new(2.0/7.0, 3.0/7.0, 6.0/7.0));
+        assert_eq!(Vector3D::new(0, 0, 0).normalize(), Vector3D::new(0, 0, 0));
+    }
+
+    #[test]
+    fn distance_vector3d() {
+        let a = Vector3D::new(1, 2, 3);
+        let b = Vector3D::new(4, 6, 3);
+
+        assert_eq!(a.distance(b), 5.0);
+        assert_eq!(b.distance(a), 5.0);
+    }
+
+    #[test]
+    fn scalar_ops_vector3d() {
+        let a = Vector3D::new(1, -2, 3);
+
+        assert_eq!(-a, Vector3D::new(-1, 2, -3));
+        assert_eq!(a * 2.0, Vector3D::new(2, -4, 6));
+        assert_eq!(2.0 * a, Vector3D::new(2, -4, 6));
+        assert_eq!(a / 2.0, Vector3D::new(0.5, -1.0, 1.5));
+    }
+
+    #[test]
+    fn assign_ops_vector3d() {
+        let mut a = Vector3D::new(1, 2, 3);
+
+        a += Vector3D::new(1, 1, 1);
+        assert_eq!(a, Vector3D::new(2, 3, 4));
+        a -= Vector3D::new(2, 2, 2);
+        assert_eq!(a, Vector3D::new(0, 1, 2));
+        a *= 3.0;
+        assert_eq!(a, Vector3D::new(0, 3, 6));
+        a /= 3.0;
+        assert_eq!(a, Vector3D::new(0, 1, 2));
+    }
+
+    #[test]
+    fn min_max_vector3d() {
+        let a = Vector3D::new(1, 5, -3);
+        let b = Vector3D::new(2, -1, -4);
+
+        assert_eq!(a.min(b), Vector3D::new(1, -1, -4));
+        assert_eq!(a.max(b), Vector3D::new(2, 5, -3));
+    }
+
+    #[test]
+    fn lerp_vector3d() {
+        let a = Vector3D::new(0, 10, -2);
+        let b = Vector3D::new(10, 20, 2);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Vector3D::new(2.5, 12.5, -1.0));
+    }
+
+    #[test]
+    fn reflect_vector3d() {
+        let incoming = Vector3D::new(1, -1, 0);
+        let floor_normal = Vector3D::new(0, 1, 0);
+
+        assert_eq!(incoming.reflect(floor_normal), Vector3D::new(1, 1, 0));
+    }
+
+    #[test]
+    fn project_vector3d() {
+        let a = Vector3D::new(3, 4, 5);
+
+        assert_eq!(a.project(Vector3D::new(0, 2, 0)), Vector3D::new(0, 4, 0));
+        assert_eq!(a.project(Vector3D::new(1, 1, 0)), Vector3D::new(3.5, 3.5, 0.0));
     }
 }
@@ -0,0 +1,256 @@
+use std::ops::{
+    Add, Sub, Mul, Div, Neg,
+    AddAssign, SubAssign, MulAssign, DivAssign
+};
+
+use super::vector3d::Vector3D;
+
+// Homogeneous coordinates, for when w actually matters (clip space)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Vector4D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+impl Vector4D {
+    pub fn new<T: Into<f64>>(x: T, y: T, z: T, w: T) -> Self {
+        Self {
+            x: x.into(),
+            y: y.into(),
+            z: z.into(),
+            w: w.into()
+        }
+    }
+
+    // w = 1, so translation applies
+    pub fn from_point(point: Vector3D) -> Self {
+        Self::new(point.x, point.y, point.z, 1.0)
+    }
+
+    // w = 0, so translation doesn't apply
+    pub fn from_direction(direction: Vector3D) -> Self {
+        Self::new(direction.x, direction.y, direction.z, 0.0)
+    }
+
+    // Drops w without dividing
+    pub fn xyz(&self) -> Vector3D {
+        Vector3D::new(self.x, self.y, self.z)
+    }
+
+    pub fn perspective_divide(&self) -> Vector3D {
+        self.xyz() / self.w
+    }
+
+    pub fn dot(&self, rhs: Self) -> f64 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    // Zero vectors stay zero instead of turning into NaN
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 { return *self }
+
+        *self / length
+    }
+
+    // Component-wise
+    pub fn min(&self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+            w: self.w.min(other.w)
+        }
+    }
+
+    // Component-wise
+    pub fn max(&self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+            w: self.w.max(other.w)
+        }
+    }
+
+    // Interpolating before the perspective divide is what makes
+    // attribute interpolation perspective-correct
+    pub fn lerp(&self, other: Self, t: f64) -> Self {
+        *self + (other - *self) * t
+    }
+}
+
+// Treated as a point, w = 1
+impl From<Vector3D> for Vector4D {
+    fn from(vector: Vector3D) -> Self {
+        Self::from_point(vector)
+    }
+}
+
+// Drops w, use perspective_divide() to divide by it instead
+impl From<Vector4D> for Vector3D {
+    fn from(vector: Vector4D) -> Self {
+        vector.xyz()
+    }
+}
+
+
+impl Add for Vector4D {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+            w: self.w + rhs.w
+        }
+    }
+}
+
+impl Sub for Vector4D {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+            w: self.w - rhs.w
+        }
+    }
+}
+
+impl Neg for Vector4D {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w
+        }
+    }
+}
+
+impl Mul<f64> for Vector4D {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+            w: self.w * rhs
+        }
+    }
+}
+
+impl Mul<Vector4D> for f64 {
+    type Output = Vector4D;
+
+    fn mul(self, rhs: Vector4D) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div<f64> for Vector4D {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+            w: self.w / rhs
+        }
+    }
+}
+
+impl AddAssign for Vector4D {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vector4D {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f64> for Vector4D {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f64> for Vector4D {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_vector4d() {
+        let a = Vector4D::new(1, 2, 3, 4);
+        let b = Vector4D::new(4, 3, 2, 1);
+
+        assert_eq!(a + b, Vector4D::new(5, 5, 5, 5));
+        assert_eq!(a - b, Vector4D::new(-3, -1, 1, 3));
+        assert_eq!(-a, Vector4D::new(-1, -2, -3, -4));
+        assert_eq!(a * 2.0, Vector4D::new(2, 4, 6, 8));
+        assert_eq!(2.0 * a, Vector4D::new(2, 4, 6, 8));
+        assert_eq!(a / 2.0, Vector4D::new(0.5, 1.0, 1.5, 2.0));
+        assert_eq!(a.dot(b), 20.0);
+    }
+
+    #[test]
+    fn assign_ops_vector4d() {
+        let mut a = Vector4D::new(1, 2, 3, 4);
+        a += Vector4D::new(1, 1, 1, 1);
+        a -= Vector4D::new(2, 2, 2, 2);
+        a *= 2.0;
+        a /= 4.0;
+
+        assert_eq!(a, Vector4D::new(0.0, 0.5, 1.0, 1.5));
+    }
+
+    #[test]
+    fn helpers_vector4d() {
+        let a = Vector4D::new(0, 4, -1, 1);
+        let b = Vector4D::new(2, -4, 1, 3);
+
+        assert_eq!(a.min(b), Vector4D::new(0, -4, -1, 1));
+        assert_eq!(a.max(b), Vector4D::new(2, 4, 1, 3));
+        assert_eq!(a.lerp(b, 0.5), Vector4D::new(1, 0, 0, 2));
+        assert_eq!(Vector4D::new(1, 1, 1, 1).length(), 2.0);
+        assert_eq!(Vector4D::new(0, 0, 0, 2).normalize(), Vector4D::new(0, 0, 0, 1));
+    }
+
+    #[test]
+    fn convert_vector4d() {
+        let point = Vector3D::new(2, 4, 6);
+
+        assert_eq!(Vector4D::from(point), Vector4D::new(2, 4, 6, 1));
+        assert_eq!(Vector4D::from_direction(point).w, 0.0);
+        assert_eq!(Vector3D::from(Vector4D::new(2, 4, 6, 2)), point);
+        assert_eq!(Vector4D::new(2, 4, 6, 2).perspective_divide(), Vector3D::new(1, 2, 3));
+    }
+}
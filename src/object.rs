@@ -1,8 +1,10 @@
 use std::collections::HashMap;
-use std::f64::consts::PI;
 
 use crate::transform::Transform;
 use crate::math_utils::vector3d::Vector3D;
+use crate::math_utils::vector4d::Vector4D;
+use crate::math_utils::matrix4x4::Matrix4x4;
+use crate::math_utils::ray::Ray;
 use crate::component::{Component, ComponentType};
 
 
@@ -12,6 +14,12 @@ pub struct Object {
     components: HashMap<&'static str, Box<dyn Component>>
 }
 
+impl Default for Object {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Object {
     pub fn new() -> Self {
         Self {
@@ -31,6 +39,17 @@ impl Object {
         dyn_obj.as_any().downcast_ref::<T>()
     }
 
+    // Sorted by type name, since the map itself has no stable order
+    pub fn components(&self) -> Vec<(&'static str, &dyn Component)> {
+        let mut components = self.components
+            .iter()
+            .map(|(name, component)| (*name, component.as_ref()))
+            .collect::<Vec<(&'static str, &dyn Component)>>();
+
+        components.sort_by_key(|(name, _)| *name);
+        components
+    }
+
     pub fn get_component_mut<T>(&mut self) -> Option<&mut T>
     where T: Component + ComponentType + 'static {
         let dyn_obj = self.components.get_mut(&T::TYPE)?;
@@ -38,14 +57,26 @@ impl Object {
     }
 }
 
+// Default width of the orthographic view, in world units
+const DEFAULT_VIEW_SIZE: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionMode {
+    Perspective,
+    Orthographic
+}
+
 #[derive(Debug)]
 pub struct Camera {
     pub transform: Transform,
     near_clip_distance: f64,
     far_clip_distance: f64,
     field_of_view: f64,
+    // Width of the visible area for orthographic mode, height follows the aspect ratio
+    view_size: f64,
+    projection_mode: ProjectionMode,
     aspect_ratio: AspectRatio,
-    projection_data: ProjectionData,
+    projection_matrix: Matrix4x4,
     dirty_flag: bool
 }
 
@@ -57,41 +88,153 @@ impl Camera {
             near_clip_distance: n.into(),
             far_clip_distance: f.into(),
             field_of_view: fov.into(),
+            view_size: DEFAULT_VIEW_SIZE,
+            projection_mode: ProjectionMode::Perspective,
             aspect_ratio,
-            projection_data: ProjectionData::generate(
-                n.into(), f.into(), fov.into(), aspect_ratio
+            projection_matrix: Matrix4x4::perspective(
+                fov.into(), aspect_ratio.value(), n.into(), f.into()
             ),
             dirty_flag: false
         }
     }
 
-    pub fn projection_data(&mut self) -> &ProjectionData {
+    pub fn new_orthographic<T: Into<f64>+Copy>(n: T, f: T, view_size: T, aspect_ratio: AspectRatio) -> Self {
+        Self {
+            transform: Transform::new(),
+            near_clip_distance: n.into(),
+            far_clip_distance: f.into(),
+            // Only used if the camera gets switched to perspective later
+            field_of_view: 90.0,
+            view_size: view_size.into(),
+            projection_mode: ProjectionMode::Orthographic,
+            aspect_ratio,
+            projection_matrix: Matrix4x4::orthographic(
+                view_size.into(), view_size.into() / aspect_ratio.value(), n.into(), f.into()
+            ),
+            dirty_flag: false
+        }
+    }
+
+    pub fn projection_mode(&self) -> ProjectionMode {
+        self.projection_mode
+    }
+
+    // Both modes keep their own settings, so switching back and forth is lossless
+    pub fn set_projection_mode(&mut self, mode: ProjectionMode) {
+        self.projection_mode = mode;
+        self.dirty_flag = true;
+    }
+
+    pub fn view_size(&self) -> f64 {
+        self.view_size
+    }
+
+    pub fn set_view_size(&mut self, view_size: f64) {
+        self.view_size = view_size;
+        self.dirty_flag = true;
+    }
+
+    // All the setters below only mark the projection matrix as dirty,
+    // it gets regenerated the next time it's needed
+
+    pub fn near_clip_distance(&self) -> f64 {
+        self.near_clip_distance
+    }
+
+    pub fn set_near_clip_distance(&mut self, near_clip_distance: f64) {
+        self.near_clip_distance = near_clip_distance;
+        self.dirty_flag = true;
+    }
+
+    pub fn far_clip_distance(&self) -> f64 {
+        self.far_clip_distance
+    }
+
+    pub fn set_far_clip_distance(&mut self, far_clip_distance: f64) {
+        self.far_clip_distance = far_clip_distance;
+        self.dirty_flag = true;
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn set_field_of_view(&mut self, field_of_view: f64) {
+        self.field_of_view = field_of_view;
+        self.dirty_flag = true;
+    }
+
+    pub fn aspect_ratio(&self) -> AspectRatio {
+        self.aspect_ratio
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: AspectRatio) {
+        self.aspect_ratio = aspect_ratio;
+        self.dirty_flag = true;
+    }
+
+    // Camera space to clip space, for row vectors like every other matrix
+    pub fn projection_matrix(&mut self) -> Matrix4x4 {
         if self.dirty_flag {
-            self.projection_data = ProjectionData::generate(
-                self.near_clip_distance, 
-                self.far_clip_distance,
-                self.field_of_view,
-                self.aspect_ratio
-            )
+            self.projection_matrix = match self.projection_mode {
+                ProjectionMode::Perspective => Matrix4x4::perspective(
+                    self.field_of_view,
+                    self.aspect_ratio.value(),
+                    self.near_clip_distance,
+                    self.far_clip_distance
+                ),
+                ProjectionMode::Orthographic => Matrix4x4::orthographic(
+                    self.view_size,
+                    self.view_size / self.aspect_ratio.value(),
+                    self.near_clip_distance,
+                    self.far_clip_distance
+                )
+            };
+            self.dirty_flag = false;
         }
 
-        &self.projection_data
+        self.projection_matrix
+    }
+
+    // The camera's transform places it in the world,
+    // so going from world to camera space is the inverse
+    pub fn view_matrix(&self) -> Matrix4x4 {
+        self.transform.inverse_matrix()
+    }
+
+    // Camera space to homogeneous clip space, before the perspective divide
+    // w is -z for perspective and 1 for orthographic
+    pub fn project_to_clip_space(&mut self, point: Vector3D) -> Vector4D {
+        Vector4D::from_point(point) * self.projection_matrix()
     }
 
     pub fn project_to_screen_space(&mut self, point: Vector3D) -> Vector3D {
-        // Deconstructing the data
-        // What the actual frick
-        let ProjectionData(w_scaler, h_scaler, m1, m2) = self.projection_data();
-        let x = point.x * w_scaler;
-        let y = point.y * h_scaler;
-        let z = (point.z * m1) + m2;
-        let w = -point.z;
-
-        Vector3D {
-            x: x/w,
-            y: y/w,
-            z: z/w,
-        }
+        self.project_to_clip_space(point).perspective_divide()
+    }
+
+    // Inverse of project_to_screen_space followed by Renderer::to_pixel_coords,
+    // giving the world space ray that goes from the camera through the pixel
+    pub fn pixel_to_ray(&mut self, x: f64, y: f64, width: usize, height: usize) -> Ray {
+        let screen_x = (x / width as f64) * 2.0 - 1.0;
+        let screen_y = (y / height as f64) * 2.0 - 1.0;
+
+        let projection = self.projection_matrix();
+        let (w_scaler, h_scaler) = (projection[0][0], projection[1][1]);
+        let ray_in_cam = match self.projection_mode {
+            // Undo the projection for a point 1 unit in front of the camera
+            ProjectionMode::Perspective => Ray::new(
+                Vector3D::new(0, 0, 0),
+                Vector3D::new(-screen_x / w_scaler, -screen_y / h_scaler, 1.0)
+            ),
+            // All rays are parallel, only the starting point moves
+            ProjectionMode::Orthographic => Ray::new(
+                Vector3D::new(screen_x / w_scaler, screen_y / h_scaler, 0.0),
+                Vector3D::new(0, 0, 1)
+            )
+        };
+
+        let ray_in_world = ray_in_cam.transform(&self.transform.matrix());
+        Ray::new(ray_in_world.origin, ray_in_world.direction)
     }
 }
 
@@ -99,21 +242,10 @@ impl Camera {
 #[derive(Debug, Clone, Copy)]
 pub struct AspectRatio(pub f64, pub f64);
 
-
-#[derive(Debug)]
-pub struct ProjectionData(f64, f64, f64, f64);
-
-// I'm just gonna hard code the aspect ratio lol
-impl ProjectionData {
-    pub fn generate(n: f64, f: f64, fov: f64, ratio: AspectRatio) -> ProjectionData {
-        let fov_tan_val = (fov/2.0 * PI/180.0).tan();
-        let near_far_interval = f - n;
-        ProjectionData(
-            1.0 / (fov_tan_val),
-            ratio.0 / (ratio.1*fov_tan_val),
-            -f / near_far_interval,
-            f*n / near_far_interval
-        )
+impl AspectRatio {
+    // Width over height, so 16:9 and 1280:720 compare the same
+    pub fn value(&self) -> f64 {
+        self.0 / self.1
     }
 }
 
@@ -123,6 +255,7 @@ mod tests {
     use std::any::Any;
 
     use crate::component::{Component, ComponentType};
+    use crate::test_utils::round_place;
     use lib_derive::{Component, ComponentType};
 
     use super::*;
@@ -133,6 +266,125 @@ mod tests {
         assert_eq!(obj.transform, Transform::new());
     }
 
+    #[test]
+    fn center_pixel_ray() {
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        camera.transform.set_position(Vector3D::new(1, 2, 3));
+        let ray = camera.pixel_to_ray(320.0, 180.0, 640, 360);
+
+        assert_eq!(ray.origin, Vector3D::new(1, 2, 3));
+        assert_eq!(ray.direction, Vector3D::new(0, 0, 1));
+    }
+
+    #[test]
+    fn pixel_ray_goes_through_projected_point() {
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let (width, height) = (640, 360);
+        let point = Vector3D::new(1.5, -0.5, 6.0);
+
+        // Same steps as the renderer, minus the rounding to whole pixels
+        let screen = camera.project_to_screen_space(point);
+        let pixel_x = (screen.x + 1.0) * 0.5 * width as f64;
+        let pixel_y = (screen.y + 1.0) * 0.5 * height as f64;
+
+        let ray = camera.pixel_to_ray(pixel_x, pixel_y, width, height);
+        let on_ray = ray.at(point.distance(ray.origin));
+
+        assert_eq!(round_place(on_ray.x, 6), point.x);
+        assert_eq!(round_place(on_ray.y, 6), point.y);
+        assert_eq!(round_place(on_ray.z, 6), point.z);
+    }
+
+    #[test]
+    fn clip_space_keeps_w() {
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let point = Vector3D::new(2, 1, 4);
+        let clip = camera.project_to_clip_space(point);
+
+        assert_eq!(clip.w, -4.0);
+        assert_eq!(clip.perspective_divide(), camera.project_to_screen_space(point));
+    }
+
+    #[test]
+    fn orthographic_projection() {
+        let mut camera = Camera::new_orthographic(1, 11, 16, AspectRatio(16.0, 9.0));
+
+        let near_point = camera.project_to_screen_space(Vector3D::new(4.0, 2.25, 1.0));
+        let far_point = camera.project_to_screen_space(Vector3D::new(4.0, 2.25, 11.0));
+
+        // Size on screen doesn't change with distance
+        assert_eq!(near_point.x, far_point.x);
+        assert_eq!(near_point.y, far_point.y);
+        assert_eq!(near_point, Vector3D::new(0.5, -0.5, 0.0));
+        assert_eq!(far_point.z, 1.0);
+    }
+
+    #[test]
+    fn orthographic_pixel_rays_are_parallel() {
+        let mut camera = Camera::new_orthographic(1, 30, 16, AspectRatio(16.0, 9.0));
+        let center = camera.pixel_to_ray(320.0, 180.0, 640, 360);
+        let corner = camera.pixel_to_ray(0.0, 0.0, 640, 360);
+
+        assert_eq!(center.origin, Vector3D::new(0, 0, 0));
+        assert_eq!(center.direction, corner.direction);
+        // Top left pixel
+        assert_eq!(corner.origin, Vector3D::new(-8.0, 4.5, 0.0));
+    }
+
+    #[test]
+    fn positive_x_is_on_the_right() {
+        // Camera space is left handed with +X on the right, the screen has to agree
+        // or everything is mirrored and the controllers turn the wrong way
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let point = Vector3D::new(2, 0, 10);
+        assert!(camera.project_to_screen_space(point).x > 0.0);
+        assert!(camera.pixel_to_ray(600.0, 180.0, 640, 360).direction.x > 0.0);
+
+        camera.set_projection_mode(ProjectionMode::Orthographic);
+        assert!(camera.project_to_screen_space(point).x > 0.0);
+        assert!(camera.pixel_to_ray(600.0, 180.0, 640, 360).origin.x > 0.0);
+    }
+
+    #[test]
+    fn switch_projection_mode() {
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let point = Vector3D::new(1, 1, 10);
+        let perspective = camera.project_to_screen_space(point);
+
+        camera.set_projection_mode(ProjectionMode::Orthographic);
+        camera.set_view_size(4.0);
+        assert_eq!(camera.projection_mode(), ProjectionMode::Orthographic);
+        assert_eq!(camera.project_to_screen_space(point).x, 0.5);
+
+        camera.set_projection_mode(ProjectionMode::Perspective);
+        assert_eq!(camera.project_to_screen_space(point), perspective);
+    }
+
+    #[test]
+    fn camera_setters_regenerate_projection() {
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        let point = Vector3D::new(1, 1, 10);
+        let before = camera.project_to_screen_space(point);
+
+        camera.set_field_of_view(60.0);
+        let narrower = camera.project_to_screen_space(point);
+        assert!(narrower.x.abs() > before.x.abs());
+
+        camera.set_aspect_ratio(AspectRatio(4.0, 3.0));
+        let projection = camera.projection_matrix();
+        assert_eq!(round_place(projection[1][1] / -projection[0][0], 5), round_place(4.0 / 3.0, 5));
+
+        camera.set_near_clip_distance(2.0);
+        camera.set_far_clip_distance(12.0);
+        assert_eq!(camera.project_to_screen_space(Vector3D::new(0, 0, 2)).z, 0.0);
+        assert_eq!(round_place(camera.project_to_screen_space(Vector3D::new(0, 0, 12)).z, 5), 1.0);
+    }
+
+    #[test]
+    fn aspect_ratio_value() {
+        assert_eq!(AspectRatio(16.0, 9.0).value(), AspectRatio(1280.0, 720.0).value());
+    }
+
     #[derive(Debug, Component, ComponentType, PartialEq)]
     struct TestComponent {}
 
@@ -167,7 +419,7 @@ mod tests {
 
         obj.add_component(comp);
 
-        let mut mutable_comp = obj
+        let mutable_comp = obj
             .get_component_mut::<ComponentWithField>()
             .unwrap();
         mutable_comp.name = "Za Warudo!".to_string();
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::app::FrameStats;
+use crate::renderer::{Renderer, Color};
+use crate::text::{self, Font, TextStyle};
+
+const MARGIN: isize = 4;
+// In pixels, the graph's top is 2 frames at 60 FPS, with a line at 1 frame
+const GRAPH_HEIGHT: usize = 40;
+const GRAPH_RANGE_MS: f64 = 1000.0 / 30.0;
+const TARGET_FRAME_MS: f64 = 1000.0 / 60.0;
+
+const BACKGROUND: (u8, u8, u8) = (20, 20, 24);
+const TEXT: (u8, u8, u8) = (230, 230, 230);
+const GRAPH_GOOD: (u8, u8, u8) = (80, 200, 80);
+const GRAPH_SLOW: (u8, u8, u8) = (230, 180, 40);
+const GRAPH_BAD: (u8, u8, u8) = (220, 60, 60);
+const GRAPH_TARGET: (u8, u8, u8) = (90, 90, 100);
+
+// Frame and render statistics drawn over the top left corner of the frame
+// Has to be drawn after the scene, since it reads the renderer's stats for that frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    // Size of a font pixel, in screen pixels
+    pub scale: usize
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugOverlay {
+    // Hidden until toggled
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            scale: 2
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // stages are extra timings the renderer can't know about, eg. the update or presenting
+    pub fn draw(&self, renderer: &mut Renderer, frame_stats: &FrameStats, stages: &[(&str, Duration)]) {
+        if !self.visible { return }
+
+        let render_stats = *renderer.stats();
+        let mut lines = vec![
+            format!("FPS {:.1}", frame_stats.fps()),
+            format!("Frame {:.2} ms  min {:.2}  max {:.2}",
+                millis(frame_stats.average()), millis(frame_stats.min()), millis(frame_stats.max())),
+            format!("Tris {}  culled {}  drawn {}",
+                render_stats.triangles_submitted, render_stats.triangles_culled, render_stats.triangles_rasterized),
+            format!("Pixels {}", render_stats.pixels_written),
+            format!("Vertex {:.2} ms", millis(render_stats.vertex_time)),
+            format!("Raster {:.2} ms", millis(render_stats.raster_time)),
+            format!("Post {:.2} ms", millis(render_stats.post_time))
+        ];
+        lines.extend(stages.iter().map(|(name, time)| format!("{} {:.2} ms", name, millis(*time))));
+
+        let font = Font::builtin();
+        let text = lines.join("\n");
+        let style = TextStyle::new(rgb(TEXT)).scale(self.scale);
+        let graph_width = frame_stats.frame_times().count();
+
+        let width = (font.text_width(&text) * self.scale).max(graph_width) as isize + MARGIN * 2;
+        let height = (font.text_height(&text) * self.scale + GRAPH_HEIGHT) as isize + MARGIN * 3;
+        renderer.fill_rect(0, 0, width, height, rgb(BACKGROUND));
+        text::draw_text(renderer, font, MARGIN, MARGIN, &text, &style);
+
+        let graph_bottom = height - MARGIN;
+        self.draw_graph(renderer, frame_stats, MARGIN, graph_bottom, GRAPH_HEIGHT as isize);
+    }
+
+    // One pixel wide bar per frame, oldest on the left
+    fn draw_graph(&self, renderer: &mut Renderer, frame_stats: &FrameStats, x: isize, bottom: isize, height: isize) {
+        let to_height = |ms: f64| ((ms / GRAPH_RANGE_MS).min(1.0) * height as f64).round() as isize;
+
+        for (i, &frame_time) in frame_stats.frame_times().enumerate() {
+            let ms = millis(frame_time);
+            let color = if ms <= TARGET_FRAME_MS * 1.05 {
+                GRAPH_GOOD
+            } else if ms <= GRAPH_RANGE_MS {
+                GRAPH_SLOW
+            } else {
+                GRAPH_BAD
+            };
+
+            let bar_height = to_height(ms);
+            renderer.fill_rect(x + i as isize, bottom - bar_height, 1, bar_height, rgb(color));
+        }
+
+        let target_y = bottom - to_height(TARGET_FRAME_MS);
+        let graph_width = frame_stats.frame_times().count() as isize;
+        renderer.fill_rect(x, target_y, graph_width, 1, rgb(GRAPH_TARGET));
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn rgb(color: (u8, u8, u8)) -> Color {
+    Color::new(color.0, color.1, color.2)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_draws_nothing() {
+        let mut renderer = Renderer::new(200, 200);
+        let overlay = DebugOverlay::new();
+        overlay.draw(&mut renderer, &FrameStats::new(), &[]);
+
+        assert!(renderer.buffer().iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn draws_in_the_corner() {
+        let mut renderer = Renderer::new(400, 300);
+        let mut overlay = DebugOverlay::new();
+        overlay.toggle();
+
+        let mut frame_stats = FrameStats::new();
+        for millis in [10, 16, 20, 50] {
+            frame_stats.push(Duration::from_millis(millis));
+        }
+        overlay.draw(&mut renderer, &frame_stats, &[("Update", Duration::from_micros(300))]);
+
+        let buffer = renderer.buffer();
+        assert_eq!(buffer[0], rgb(BACKGROUND).rgb_u32());
+        assert!(buffer.contains(&rgb(TEXT).rgb_u32()));
+        assert!(buffer.contains(&rgb(GRAPH_BAD).rgb_u32()));
+        // Nothing in the opposite corner
+        assert_eq!(buffer[buffer.len() - 1], 0);
+    }
+
+    #[test]
+    fn fits_tiny_framebuffers() {
+        let mut renderer = Renderer::new(3, 2);
+        let mut overlay = DebugOverlay::new();
+        overlay.toggle();
+        overlay.draw(&mut renderer, &FrameStats::new(), &[]);
+    }
+}
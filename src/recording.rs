@@ -0,0 +1,220 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+
+use crate::input::{InputEvent, parse_button, button_name};
+
+// Everything the input did during one frame, and how long that frame took
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputFrame {
+    // In microseconds, same as the runner's delta_time
+    pub delta_time: u128,
+    pub events: Vec<InputEvent>
+}
+
+// A session's input, frame by frame, so it can be played back exactly
+// Replaying feeds the recorded delta times instead of the wall clock,
+// so the simulation steps the same no matter how fast the replaying machine is
+//
+// Recording files are plain text:
+//
+//   size <width> <height>
+//   frame <delta time in microseconds>
+//   press <button>
+//   release <button>
+//   cursor <x> <y>
+//   leave
+//   wheel <steps>
+//   unfocus
+//
+// Events belong to the last frame declared, buttons are named like in InputMap files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    // Framebuffer size the session started with, for headless playback
+    pub width: usize,
+    pub height: usize,
+    pub frames: Vec<InputFrame>,
+    current: Vec<InputEvent>
+}
+
+impl Recording {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            frames: vec![],
+            current: vec![]
+        }
+    }
+
+    // Events are held back until end_frame() knows how long the frame took
+    pub fn record(&mut self, event: InputEvent) {
+        self.current.push(event);
+    }
+
+    pub fn end_frame(&mut self, delta_time: u128) {
+        self.frames.push(InputFrame {
+            delta_time,
+            events: std::mem::take(&mut self.current)
+        });
+    }
+
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(file_path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut recording: Option<Self> = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let error = |message: &str| io::Error::new(
+                io::ErrorKind::InvalidData, format!("Line {}: {}", line_number + 1, message)
+            );
+            let number = |token: &str| token.parse::<f64>().map_err(|_| error(&format!("Invalid number '{}'", token)));
+            let button = |name: &str| parse_button(name).ok_or_else(|| error(&format!("Unknown button '{}'", name)));
+
+            let tokens = line.split_whitespace().collect::<Vec<&str>>();
+            if let ["size", width, height] = tokens.as_slice() {
+                let size = |token: &str| token.parse::<usize>().map_err(|_| error(&format!("Invalid size '{}'", token)));
+                recording = Some(Self::new(size(width)?, size(height)?));
+                continue;
+            }
+
+            let recording = recording.as_mut().ok_or_else(|| error("Expected the size first"))?;
+            if let ["frame", delta_time] = tokens.as_slice() {
+                let delta_time = delta_time.parse::<u128>().map_err(|_| error(&format!("Invalid delta time '{}'", delta_time)))?;
+                recording.frames.push(InputFrame { delta_time, events: vec![] });
+                continue;
+            }
+
+            let frame = recording.frames.last_mut().ok_or_else(|| error("Event given before any frame"))?;
+            let event = match tokens.as_slice() {
+                ["press", name] => InputEvent::Pressed(button(name)?),
+                ["release", name] => InputEvent::Released(button(name)?),
+                ["cursor", x, y] => InputEvent::CursorMoved(number(x)?, number(y)?),
+                ["leave"] => InputEvent::CursorLeft,
+                ["wheel", steps] => InputEvent::Wheel(number(steps)?),
+                ["unfocus"] => InputEvent::FocusLost,
+                [keyword, ..] => return Err(error(&format!("Unknown or malformed statement '{}'", keyword))),
+                [] => unreachable!()
+            };
+            frame.events.push(event);
+        }
+
+        recording.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No size found."))
+    }
+
+    // Events of the frame still in progress are left out
+    pub fn save(&self, file_path: &str) -> io::Result<()> {
+        let mut file = File::create(file_path)?;
+        writeln!(file, "size {} {}", self.width, self.height)?;
+
+        for frame in self.frames.iter() {
+            writeln!(file, "frame {}", frame.delta_time)?;
+
+            for event in frame.events.iter() {
+                // Buttons without a name can't be bound either, so gameplay never sees them
+                match *event {
+                    InputEvent::Pressed(button) => if let Some(name) = button_name(button) {
+                        writeln!(file, "press {}", name)?;
+                    },
+                    InputEvent::Released(button) => if let Some(name) = button_name(button) {
+                        writeln!(file, "release {}", name)?;
+                    },
+                    InputEvent::CursorMoved(x, y) => writeln!(file, "cursor {} {}", x, y)?,
+                    InputEvent::CursorLeft => writeln!(file, "leave")?,
+                    InputEvent::Wheel(steps) => writeln!(file, "wheel {}", steps)?,
+                    InputEvent::FocusLost => writeln!(file, "unfocus")?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use winit::event::{VirtualKeyCode, MouseButton};
+    use crate::input::{Button, Input};
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("zesty_{}_{}", std::process::id(), name))
+            .to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut recording = Recording::new(640, 360);
+        recording.record(InputEvent::Pressed(Button::Key(VirtualKeyCode::W)));
+        recording.record(InputEvent::CursorMoved(10.25, 0.1 + 0.2));
+        recording.end_frame(16_667);
+
+        recording.end_frame(0);
+
+        recording.record(InputEvent::Released(Button::Key(VirtualKeyCode::W)));
+        recording.record(InputEvent::Pressed(Button::Mouse(MouseButton::Left)));
+        recording.record(InputEvent::Wheel(-1.5));
+        recording.record(InputEvent::CursorLeft);
+        recording.record(InputEvent::FocusLost);
+        recording.end_frame(33_334);
+
+        // Not finished yet, so not saved
+        recording.record(InputEvent::Wheel(1.0));
+
+        let path = temp_path("recording.txt");
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (640, 360));
+        // Floats have to come back bit for bit, or the replay drifts
+        assert_eq!(loaded.frames, recording.frames);
+    }
+
+    #[test]
+    fn replay_matches_live_input() {
+        let mut live = Input::default();
+        let mut recording = Recording::new(1, 1);
+
+        let events = [
+            InputEvent::CursorMoved(5.0, 5.0),
+            InputEvent::Pressed(Button::Key(VirtualKeyCode::D)),
+            InputEvent::CursorMoved(8.5, 4.0)
+        ];
+        for event in events {
+            live.apply(event);
+            recording.record(event);
+        }
+        recording.end_frame(1000);
+
+        let mut replayed = Input::default();
+        for event in recording.frames[0].events.iter() {
+            replayed.apply(*event);
+        }
+
+        assert_eq!(replayed.mouse_delta(), live.mouse_delta());
+        assert!(replayed.was_pressed(Button::Key(VirtualKeyCode::D)));
+    }
+
+    #[test]
+    fn recording_errors() {
+        let error = Recording::parse("frame 10\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Expected the size first");
+
+        let error = Recording::parse("size 4 4\npress A\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Event given before any frame");
+
+        let error = Recording::parse("size 4 4\nframe 10\ncursor 1 x\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 3: Invalid number 'x'");
+
+        let error = Recording::parse("").unwrap_err();
+        assert_eq!(error.to_string(), "No size found.");
+    }
+}
@@ -1,33 +1,115 @@
-use std::collections::HashMap;
-use std::f64::consts::PI;
 use std::cmp::{min, max};
+use std::time::{Duration, Instant};
 
 use crate::scene::Scene;
-use crate::math_utils::{vector3d::Vector3D, matrix4x4};
-use matrix4x4::{matrix_multiply, vector_matrix_multiply};
+use crate::object::{Object, Camera, AspectRatio};
+use crate::math_utils::vector3d::Vector3D;
 use crate::component::mesh::Mesh;
+use crate::component::material::Material;
+
+mod draw2d;
+mod hdr;
+pub mod color;
+pub mod image;
+pub mod lut;
+pub mod post;
+
+pub use image::Image;
+pub use color::{Color, LinearColor};
+pub use hdr::{HdrSettings, ToneMapping};
+pub use lut::Lut;
+pub use post::PostEffect;
+
+// How a color is combined with what's already in the framebuffer
+// Everything but Opaque scales the color's effect by its alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    // Replaces, alpha is ignored
+    #[default]
+    Opaque,
+    // Mixes over, eg. glass
+    Alpha,
+    // Brightens, eg. fire and glows
+    Additive,
+    // Darkens, eg. tinted windows and shadows
+    Multiply
+}
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub struct Color(u32);
+impl BlendMode {
+    // As written in scene files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Opaque => "opaque",
+            Self::Alpha => "alpha",
+            Self::Additive => "additive",
+            Self::Multiply => "multiply"
+        }
+    }
 
-impl Color {
-    pub fn new<T: TryInto<u8>>(r: T, g: T, b: T) -> Self {
-        Self(
-            r.try_into().unwrap_or_default() as u32 |
-            ((g.try_into().unwrap_or_default() as u32) << 8) |
-            ((b.try_into().unwrap_or_default() as u32) << 16)
-        )
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Opaque, Self::Alpha, Self::Additive, Self::Multiply].into_iter()
+            .find(|mode| mode.name() == name)
     }
 
-    pub fn rgb_u32(&self) -> u32 {
-        self.0
+    // source drawn over destination, the result is opaque
+    pub fn blend(&self, source: Color, destination: Color) -> Color {
+        let alpha = source.alpha() as f64 / 255.0;
+        let [sr, sg, sb] = source.components();
+        let [dr, dg, db] = destination.components();
+        let per_channel = |f: &dyn Fn(f64, f64) -> f64| {
+            let channel = |s: u8, d: u8| f(s as f64, d as f64).round().clamp(0.0, 255.0) as u8;
+            Color::new(channel(sr, dr), channel(sg, dg), channel(sb, db))
+        };
+
+        match self {
+            Self::Opaque => source.with_alpha(255),
+            Self::Alpha => destination.with_alpha(255).lerp(source.with_alpha(255), alpha),
+            Self::Additive => per_channel(&|s, d| d + s * alpha),
+            Self::Multiply => per_channel(&|s, d| d * (1.0 - alpha + alpha * s / 255.0))
+        }
+    }
+
+    // Same as blend(), in linear light and without clamping, for the HDR buffer
+    pub fn blend_linear(&self, source: LinearColor, destination: LinearColor) -> LinearColor {
+        let alpha = source.a;
+        let opaque = |color: LinearColor| LinearColor { a: 1.0, ..color };
+
+        match self {
+            Self::Opaque => opaque(source),
+            Self::Alpha => opaque(destination.lerp(source, alpha)),
+            Self::Additive => opaque(destination + source * alpha),
+            Self::Multiply => opaque(destination * (LinearColor::WHITE * (1.0 - alpha) + source * alpha))
+        }
     }
 }
 
+// What the last render() did, for profiling
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderStats {
+    pub triangles_submitted: usize,
+    // Behind the near plane, facing away or entirely off screen
+    pub triangles_culled: usize,
+    pub triangles_rasterized: usize,
+    pub pixels_written: usize,
+    // Transforming and projecting vertices
+    pub vertex_time: Duration,
+    // Filling triangles, including the culling checks
+    pub raster_time: Duration,
+    // Set by post_process(), which runs after render()
+    pub post_time: Duration
+}
+
 pub struct Renderer {
     width: usize,
     height: usize,
-    tmp_buffer: Vec<u32>
+    tmp_buffer: Vec<u32>,
+    // Screen space depth of the closest surface per pixel, 0 at the near plane to 1 at the far one
+    depth_buffer: Vec<f64>,
+    // When on, render() goes here instead of tmp_buffer, see resolve()
+    hdr_buffer: Option<Vec<LinearColor>>,
+    hdr_settings: HdrSettings,
+    post_effects: Vec<PostEffect>,
+    stats: RenderStats
 }
 
 impl Renderer {
@@ -35,14 +117,80 @@ impl Renderer {
         Self {
             width,
             height,
-            tmp_buffer: vec![0; width*height]
+            tmp_buffer: vec![0; width*height],
+            depth_buffer: vec![f64::INFINITY; width*height],
+            hdr_buffer: None,
+            hdr_settings: HdrSettings::default(),
+            post_effects: vec![],
+            stats: RenderStats::default()
         }
     }
 
+    // Reallocates the framebuffer, old content is thrown away
+    // The camera's aspect ratio catches up on the next render
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width == self.width && height == self.height { return }
+
+        self.width = width;
+        self.height = height;
+        self.tmp_buffer = vec![0; width*height];
+        self.depth_buffer = vec![f64::INFINITY; width*height];
+        if self.hdr_buffer.is_some() {
+            self.hdr_buffer = Some(vec![LinearColor::BLACK; width*height]);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
     pub fn render(&mut self, scene: &mut Scene) {
-        let rot = (PI/4.0) * (1.0/200.0);
+        self.stats = RenderStats::default();
+        self.depth_buffer.iter_mut().for_each(|depth| *depth = f64::INFINITY);
         let camera = &mut scene.camera;
 
+        // Keep the camera in sync with the framebuffer, so the image never stretches
+        let aspect_ratio = AspectRatio(self.width as f64, self.height as f64);
+        if camera.aspect_ratio().value() != aspect_ratio.value() {
+            camera.set_aspect_ratio(aspect_ratio);
+        }
+
+        let view_matrix = camera.view_matrix();
+
+        // Objects without a mesh (empties, lights later on) have nothing to draw
+        let (transparent, opaque): (Vec<&Object>, Vec<&Object>) = scene.objects.iter()
+            .filter(|obj| obj.get_component::<Mesh>().is_some())
+            .partition(|obj| obj.get_component::<Material>().is_some_and(|material| material.is_transparent()));
+
+        for obj in opaque {
+            self.draw_object(obj, camera);
+        }
+
+        // Blending isn't order independent and transparent objects don't write depth,
+        // so they go furthest first to end up on top of what's behind them
+        let mut transparent = transparent.into_iter()
+            .map(|obj| (view_matrix.transform_point(obj.transform.position()).z, obj))
+            .collect::<Vec<(f64, &Object)>>();
+        transparent.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for (_, obj) in transparent {
+            self.draw_object(obj, camera);
+        }
+    }
+
+    fn draw_object(&mut self, obj: &Object, camera: &mut Camera) {
+        let mesh = obj.get_component::<Mesh>().unwrap();
+        let material = obj.get_component::<Material>();
+
+        // Per triangle colors for meshes without a material, so the faces can be told apart
         let color_list: [Color; 8] = [
             Color::new(255, 255, 255),
             Color::new(0, 0, 255),
@@ -53,53 +201,71 @@ impl Renderer {
             Color::new(255, 255, 0),
             Color::new(100, 100, 100)
         ];
+        let near_clip_distance = camera.near_clip_distance();
+        let obj_to_cam_matrix = obj.transform.matrix() * camera.view_matrix();
+
+        let vertex_start = Instant::now();
+        // (camera space, pixel coords, depth) for every vertex
+        let projected_vertices = mesh.vertices().iter().map(|&vertex| {
+            let vertex_in_cam = obj_to_cam_matrix.transform_point(vertex);
+            let screen = camera.project_to_screen_space(vertex_in_cam);
+            (vertex_in_cam, self.to_pixel_coords(screen), screen.z)
+        }).collect::<Vec<(Vector3D, (isize, isize), f64)>>();
+        self.stats.vertex_time += vertex_start.elapsed();
+
+        let raster_start = Instant::now();
+        for (i, triangle) in mesh.triangles().iter().enumerate() {
+            self.stats.triangles_submitted += 1;
+            let triangle_vertices = triangle.map(|vertex_index| projected_vertices[vertex_index]);
+
+            // No proper clipping yet, so just drop anything
+            // poking through the near plane
+            if triangle_vertices.iter().any(|(cam, _, _)| cam.z < near_clip_distance) {
+                self.stats.triangles_culled += 1;
+                continue;
+            }
 
-        for obj in scene.objects.iter_mut() {
-            let mesh = obj.get_component::<Mesh>().unwrap();
-            let obj_to_cam_matrix = matrix_multiply(
-                &obj.transform.matrix(),
-                &camera.transform.matrix()
-            );
-
-            let all_vertices = mesh.vertices();
-            let mut obj_vertex_loopkup: HashMap<usize, Vector3D> = HashMap::new();
-
-            let mut i = 0;
-            
-            for triangle in mesh.triangles() {
-                let triangle_vertices = triangle.iter().map(|&vertex_index| {
-                    if let Some(i) = obj_vertex_loopkup.get(&vertex_index) {
-                        return *i;
-                    }
+            let triangle_tuple = triangle_vertices.map(|(_, pixel, _)| pixel);
+            if is_back_facing(&triangle_tuple) || !self.overlaps_screen(&triangle_tuple) {
+                self.stats.triangles_culled += 1;
+                continue;
+            }
 
-                    let vertex_in_cam = vector_matrix_multiply(
-                        &obj_to_cam_matrix,
-                        all_vertices[vertex_index],
-                        true
-                    );
+            self.stats.triangles_rasterized += 1;
+            let depths = triangle_vertices.map(|(_, _, depth)| depth);
+            let (color, blend_mode) = match material {
+                Some(material) => (LinearColor::from(material.color) * material.intensity, material.blend_mode),
+                None => (LinearColor::from(color_list[i % 8]), BlendMode::Opaque)
+            };
+            self.fill_triangle(triangle_tuple, Some(depths), color, blend_mode);
+        }
+        self.stats.raster_time += raster_start.elapsed();
+    }
 
-                    let screen_space_coords =
-                    camera.project_to_screen_space(vertex_in_cam);
+    fn overlaps_screen(&self, triangle: &[(isize, isize); 3]) -> bool {
+        let (width, height) = (self.width as isize, self.height as isize);
 
-                    obj_vertex_loopkup.insert(vertex_index, screen_space_coords);
-                    screen_space_coords
-                });
+        !(triangle.iter().all(|&(x, _)| x < 0) || triangle.iter().all(|&(x, _)| x >= width)
+        || triangle.iter().all(|&(_, y)| y < 0) || triangle.iter().all(|&(_, y)| y >= height))
+    }
 
-                let triangle_tuple = triangle_vertices.map(|point| {
-                    let ncd_coords = self.to_ncd_space(point);
+    // World space point to framebuffer coords, or None if it's behind the near plane
+    // Follows the same path as render()
+    pub fn project_to_pixel(&self, camera: &mut Camera, point: Vector3D) -> Option<(isize, isize)> {
+        let point_in_cam = camera.view_matrix().transform_point(point);
+        if point_in_cam.z < camera.near_clip_distance() { return None }
 
-                    let final_x = (ncd_coords.x * self.width as f64) as isize;
-                    let final_y = (ncd_coords.y * self.height as f64) as isize;
+        Some(self.to_pixel_coords(camera.project_to_screen_space(point_in_cam)))
+    }
 
-                    (final_x, final_y)
-                }).collect::<Vec<(isize, isize)>>();
+    // Screen space point (-1..1 on both axes) to framebuffer coordinates
+    pub fn to_pixel_coords(&self, point: Vector3D) -> (isize, isize) {
+        let ncd_coords = self.to_ncd_space(point);
 
-                self.draw_triangles(triangle_tuple, color_list[i % 8]);
-                i += 1;
-            }
+        let final_x = (ncd_coords.x * self.width as f64) as isize;
+        let final_y = (ncd_coords.y * self.height as f64) as isize;
 
-            obj.transform.rotate(rot, 0.0, rot);
-        }
+        (final_x, final_y)
     }
 
     fn to_ncd_space(&self, vector: Vector3D) -> Vector3D {
@@ -114,12 +280,32 @@ impl Renderer {
         &self.tmp_buffer
     }
 
+    // Depth of whatever render() drew at the pixel, infinity where nothing was
+    pub fn depth_at(&self, x: usize, y: usize) -> Option<f64> {
+        if x >= self.width || y >= self.height { return None }
+        Some(self.depth_buffer[x + y * self.width])
+    }
+
     pub fn clear_tmp_buffer(&mut self) {
         self.tmp_buffer.iter_mut().for_each(|x| *x = 0);
+        if let Some(hdr_buffer) = &mut self.hdr_buffer {
+            hdr_buffer.iter_mut().for_each(|pixel| *pixel = LinearColor::BLACK);
+        }
     }
 
+    // Anything outside the framebuffer is silently dropped
     pub fn plot_pixel<T: Into<usize>>(&mut self, x: T, y: T, color: Color) {
-        self.tmp_buffer[x.into() + (y.into()*self.width)] = color.rgb_u32();
+        let (x, y) = (x.into(), y.into());
+        if x >= self.width || y >= self.height { return }
+
+        self.tmp_buffer[x + (y*self.width)] = color.rgb_u32();
+    }
+
+    pub fn plot_pixel_blended(&mut self, x: usize, y: usize, color: Color, blend_mode: BlendMode) {
+        if x >= self.width || y >= self.height { return }
+
+        let pixel = &mut self.tmp_buffer[x + y * self.width];
+        *pixel = blend_mode.blend(color, Color::from_rgb_u32(*pixel)).rgb_u32();
     }
 
     pub fn bresenham_line(
@@ -156,16 +342,25 @@ impl Renderer {
         }
     }
 
+    // Fills without touching the depth buffer
     pub fn draw_triangles(&mut self, triangle_tuple: Vec<(isize, isize)>, color: Color) {
+        let triangle = [triangle_tuple[0], triangle_tuple[1], triangle_tuple[2]];
+        self.fill_triangle(triangle, None, color.into(), BlendMode::Opaque);
+    }
+
+    // With depths, each pixel is depth tested, the depth is interpolated
+    // linearly in screen space, which is right for the perspective depth
+    // Only opaque triangles write their depth, so nothing is hidden behind glass
+    fn fill_triangle(&mut self, triangle_tuple: [(isize, isize); 3], depths: Option<[f64; 3]>, color: LinearColor, blend_mode: BlendMode) {
+        // Packed once, not per pixel
+        let packed = Color::from(color);
+
         let mut max_x = triangle_tuple[0].0;
         let mut max_y = triangle_tuple[0].1;
         let mut min_x = max_x;
         let mut min_y = max_y;
 
-        for index in 1..3 {
-            let x_part = triangle_tuple[index].0;
-            let y_part = triangle_tuple[index].1;
-
+        for &(x_part, y_part) in triangle_tuple.iter().skip(1) {
             max_x = max(x_part, max_x);
             min_x = min(x_part, min_x);
 
@@ -173,6 +368,12 @@ impl Renderer {
             min_y = min(y_part, min_y);
         }
 
+        // Only walk the part of the bounding box that's on screen
+        min_x = max(min_x, 0);
+        min_y = max(min_y, 0);
+        max_x = min(max_x, self.width as isize);
+        max_y = min(max_y, self.height as isize);
+
         let edge_results = triangle_tuple.iter().enumerate().map(|pair| {
             let (i, point) = pair;
             let next_point = triangle_tuple[(i+1) % 3];
@@ -186,38 +387,257 @@ impl Renderer {
 
         for offset_x in 0..(max_x - min_x) {
             for offset_y in 0..(max_y - min_y) {
-                let not_in_triangle = edge_results.iter().any(|results_group| {
+                let curr_results = edge_results.iter().map(|results_group| {
                     let (first_result, diff_x, diff_y) = results_group;
-                    let curr_result = first_result + (diff_x*offset_y) - (diff_y*offset_x);
-
-                    curr_result < 0
+                    first_result + (diff_x*offset_y) - (diff_y*offset_x)
+                }).collect::<Vec<isize>>();
+
+                // Pixels right on an edge only go to one of the two triangles sharing it,
+                // the one walking it with y going down, or x going left when flat,
+                // so blended triangles don't hit them twice
+                let not_in_triangle = curr_results.iter().zip(edge_results.iter()).any(|(&curr_result, &(_, diff_x, diff_y))| {
+                    let owns_edge = diff_y < 0 || (diff_y == 0 && diff_x > 0);
+                    curr_result > 0 || (curr_result == 0 && !owns_edge)
                 });
-
                 if not_in_triangle { continue; }
-                self.plot_pixel(
-                    (min_x + offset_x) as usize,
-                    (min_y + offset_y) as usize, color);
+
+                let (x, y) = ((min_x + offset_x) as usize, (min_y + offset_y) as usize);
+                if let Some(depths) = depths {
+                    // Each edge's result weighs the vertex opposite to it
+                    let total = curr_results.iter().sum::<isize>();
+                    let depth = if total == 0 {
+                        depths[0]
+                    } else {
+                        (0..3).map(|i| curr_results[i] as f64 * depths[(i+2) % 3]).sum::<f64>() / total as f64
+                    };
+
+                    let index = x + y * self.width;
+                    if depth >= self.depth_buffer[index] { continue; }
+                    if blend_mode == BlendMode::Opaque {
+                        self.depth_buffer[index] = depth;
+                    }
+                }
+
+                self.stats.pixels_written += 1;
+                match &mut self.hdr_buffer {
+                    Some(hdr_buffer) => {
+                        let pixel = &mut hdr_buffer[x + y * self.width];
+                        *pixel = blend_mode.blend_linear(color, *pixel);
+                    },
+                    None => self.plot_pixel_blended(x, y, packed, blend_mode)
+                }
             }
         }
     }
 }
 
+// draw_triangles() only fills triangles wound one way round on screen,
+// checking first saves walking the bounding box for nothing
+fn is_back_facing(triangle: &[(isize, isize); 3]) -> bool {
+    let [(x0, y0), (x1, y1), (x2, y2)] = *triangle;
+    let double_area = (x1 - x0) * (y2 - y0) - (y1 - y0) * (x2 - x0);
+    double_area < 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn render_syncs_camera_aspect_ratio() {
+        let mut renderer = Renderer::new(400, 300);
+        let mut scene = Scene {
+            objects: vec![],
+            camera: Camera::new(1, 30, 90, AspectRatio(16.0, 9.0))
+        };
+
+        renderer.render(&mut scene);
+        assert_eq!(scene.camera.aspect_ratio().value(), 4.0 / 3.0);
+    }
+
+    #[test]
+    fn resize_framebuffer() {
+        let mut renderer = Renderer::new(4, 4);
+        renderer.resize(8, 2);
+
+        assert_eq!(renderer.buffer().len(), 16);
+        assert_eq!((renderer.width(), renderer.height()), (8, 2));
+
+        let color = Color::new(1, 2, 3);
+        renderer.plot_pixel(7_usize, 1_usize, color);
+        assert_eq!(renderer.buffer()[15], color.rgb_u32());
+
+        // Out of the new bounds, so nothing happens
+        renderer.plot_pixel(0_usize, 3_usize, color);
+        assert_eq!(renderer.buffer().iter().filter(|&&p| p != 0).count(), 1);
+    }
 
     #[test]
-    fn new_invalid_color() {
-        let color = Color::new(256, 34, -1);
+    fn resize_updates_camera_on_render() {
+        let mut renderer = Renderer::new(400, 300);
+        let mut scene = Scene {
+            objects: vec![],
+            camera: Camera::new(1, 30, 90, AspectRatio(4.0, 3.0))
+        };
+
+        renderer.resize(1000, 250);
+        renderer.render(&mut scene);
+        assert_eq!(scene.camera.aspect_ratio().value(), 4.0);
+    }
 
-        assert_eq!(color, Color::new(0, 34, 0));
+    #[test]
+    fn blend_modes() {
+        let background = Color::new(100, 200, 50);
+        let source = Color::rgba(200, 100, 255, 128);
+        let half = 128.0 / 255.0;
+
+        assert_eq!(BlendMode::Opaque.blend(source, background), Color::new(200, 100, 255));
+        assert_eq!(BlendMode::Alpha.blend(source, background), background.lerp(source.with_alpha(255), half));
+        assert_eq!(BlendMode::Additive.blend(source, background), Color::new(200, 250, 178));
+        assert_eq!(BlendMode::Multiply.blend(source, background), Color::new(89, 139, 50));
+
+        // Nothing happens when fully transparent
+        for mode in [BlendMode::Alpha, BlendMode::Additive, BlendMode::Multiply] {
+            assert_eq!(mode.blend(source.with_alpha(0), background), background);
+            assert_eq!(BlendMode::from_name(mode.name()), Some(mode));
+        }
+    }
+
+    fn quad(name: &str, z: f64, material: Material) -> Object {
+        let obj_path = env::temp_dir()
+            .join(format!("zesty_{}_{}.obj", std::process::id(), name))
+            .to_string_lossy()
+            .to_string();
+        fs::write(&obj_path, "v -5 -5 0\nv -5 5 0\nv 5 5 0\nv 5 -5 0\nf 1 2 3\nf 1 3 4\n").unwrap();
+
+        let mut mesh = Mesh::new();
+        mesh.load_obj(&obj_path).unwrap();
+        fs::remove_file(obj_path).unwrap();
+
+        let mut object = Object::new();
+        object.transform.set_position(Vector3D::new(0.0, 0.0, z));
+        object.add_component(mesh);
+        object.add_component(material);
+        object
     }
 
     #[test]
-    fn output_rgb_as_u32() {
-        let color = Color::new(100, 234, 88);
-        let expected = 100_u32 | (234 << 8) | (88 << 16);
+    fn positive_x_renders_on_the_right() {
+        let red = Color::new(255, 0, 0);
+        let mut wall = quad("right", 10.0, Material::new(red));
+        wall.transform.set_position(Vector3D::new(6.0, 0.0, 10.0));
+        let mut scene = Scene {
+            objects: vec![wall],
+            camera: Camera::new(1, 30, 90, AspectRatio(1.0, 1.0))
+        };
+
+        let mut renderer = Renderer::new(20, 20);
+        renderer.render(&mut scene);
+
+        // Still facing the camera after the flip, so it's filled
+        assert_eq!(renderer.buffer()[15 + 10 * 20], red.rgb_u32());
+        assert_ne!(renderer.buffer()[4 + 10 * 20], red.rgb_u32());
+    }
 
-        assert_eq!(color.rgb_u32(), expected);
+    #[test]
+    fn transparent_pass() {
+        let red = Color::new(255, 0, 0);
+        let glass = Color::rgba(0, 0, 255, 128);
+        let tint = Color::rgba(0, 255, 0, 128);
+
+        // Transparent objects listed first and nearest first, they still
+        // have to come out drawn after the wall, furthest first
+        let mut scene = Scene {
+            objects: vec![
+                quad("tint", 4.0, Material::transparent(tint, BlendMode::Alpha)),
+                quad("glass", 6.0, Material::transparent(glass, BlendMode::Alpha)),
+                quad("wall", 10.0, Material::new(red)),
+                quad("hidden", 20.0, Material::transparent(tint, BlendMode::Additive))
+            ],
+            camera: Camera::new(1, 30, 90, AspectRatio(1.0, 1.0))
+        };
+
+        let mut renderer = Renderer::new(20, 20);
+        renderer.render(&mut scene);
+
+        let expected = BlendMode::Alpha.blend(tint, BlendMode::Alpha.blend(glass, red));
+        assert_eq!(renderer.buffer()[10 + 10 * 20], expected.rgb_u32());
+
+        // The glass didn't write depth, so debug lines behind it still show
+        assert!(renderer.depth_at(10, 10).unwrap() > scene.camera.project_to_screen_space(Vector3D::new(0, 0, 6)).z);
+    }
+
+    #[test]
+    fn fill_rect_clips() {
+        let mut renderer = Renderer::new(4, 3);
+        let color = Color::new(9, 9, 9);
+
+        renderer.fill_rect(-1, 1, 3, 5, color);
+        let lit = renderer.buffer().iter().filter(|&&p| p != 0).count();
+        assert_eq!(lit, 4);
+        assert_eq!(renderer.buffer()[4], color.rgb_u32());
+
+        // Entirely outside, or empty
+        renderer.fill_rect(10, 10, 2, 2, color);
+        renderer.fill_rect(-5, 0, 2, 2, color);
+        renderer.fill_rect(0, 0, -2, 2, color);
+        assert_eq!(renderer.buffer().iter().filter(|&&p| p != 0).count(), 4);
+    }
+
+    #[test]
+    fn back_facing_triangles_draw_nothing() {
+        let mut renderer = Renderer::new(10, 10);
+        let color = Color::new(1, 1, 1);
+        let triangle = [(1, 1), (8, 1), (1, 8)];
+        let reversed = [(1, 1), (1, 8), (8, 1)];
+
+        for winding in [triangle, reversed] {
+            renderer.clear_tmp_buffer();
+            renderer.draw_triangles(winding.to_vec(), color);
+
+            let drawn = renderer.buffer().iter().any(|&p| p != 0);
+            assert_eq!(drawn, !is_back_facing(&winding));
+        }
+    }
+
+    #[test]
+    fn render_stats() {
+        // The same triangle both ways round
+        let obj_path = env::temp_dir()
+            .join(format!("zesty_{}_stats.obj", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        fs::write(&obj_path, "v -1 -1 0\nv 0 1 0\nv 1 -1 0\nf 1 2 3\nf 1 3 2\n").unwrap();
+
+        let mut mesh = Mesh::new();
+        mesh.load_obj(&obj_path).unwrap();
+        fs::remove_file(obj_path).unwrap();
+
+        let mut object = Object::new();
+        object.transform.set_position(Vector3D::new(0.0, 0.0, 5.0));
+        object.add_component(mesh);
+
+        let mut scene = Scene {
+            objects: vec![object],
+            camera: Camera::new(1, 30, 90, AspectRatio(1.0, 1.0))
+        };
+
+        let mut renderer = Renderer::new(50, 50);
+        renderer.render(&mut scene);
+        let stats = *renderer.stats();
+
+        assert_eq!(stats.triangles_submitted, 2);
+        assert_eq!(stats.triangles_culled, 1);
+        assert_eq!(stats.triangles_rasterized, 1);
+        assert_eq!(stats.pixels_written, renderer.buffer().iter().filter(|&&p| p != 0).count());
+        assert!(stats.pixels_written > 0);
+
+        // Stats are per render, not cumulative
+        scene.objects[0].transform.set_position(Vector3D::new(0.0, 0.0, -5.0));
+        renderer.render(&mut scene);
+        assert_eq!(renderer.stats().triangles_culled, 2);
+        assert_eq!(renderer.stats().pixels_written, 0);
     }
 }
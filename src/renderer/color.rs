@@ -0,0 +1,460 @@
+use std::ops::{Add, Sub, Mul, Div, AddAssign, MulAssign};
+
+// Red in the lowest byte, then green, blue and alpha, 255 is opaque
+// The framebuffer has no alpha, rgb_u32() leaves it out
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Color(u32);
+
+impl Color {
+    pub const BLACK: Color = Color::from_components(0, 0, 0, 255);
+    pub const WHITE: Color = Color::from_components(255, 255, 255, 255);
+    pub const GRAY: Color = Color::from_components(128, 128, 128, 255);
+    pub const RED: Color = Color::from_components(255, 0, 0, 255);
+    pub const GREEN: Color = Color::from_components(0, 255, 0, 255);
+    pub const BLUE: Color = Color::from_components(0, 0, 255, 255);
+    pub const YELLOW: Color = Color::from_components(255, 255, 0, 255);
+    pub const CYAN: Color = Color::from_components(0, 255, 255, 255);
+    pub const MAGENTA: Color = Color::from_components(255, 0, 255, 255);
+    pub const TRANSPARENT: Color = Color::from_components(0, 0, 0, 0);
+
+    // Opaque, channels are saturated to 0..255, so 300 is 255 and -1 is 0
+    // try_new() rejects them instead
+    pub fn new<T: TryInto<u8> + PartialOrd + Default + Copy>(r: T, g: T, b: T) -> Self {
+        Self::from_components(saturate(r), saturate(g), saturate(b), 255)
+    }
+
+    pub fn rgba<T: TryInto<u8> + PartialOrd + Default + Copy>(r: T, g: T, b: T, a: T) -> Self {
+        Self::from_components(saturate(r), saturate(g), saturate(b), saturate(a))
+    }
+
+    // None instead of saturating what doesn't fit in a u8
+    pub fn try_new<T: TryInto<u8>>(r: T, g: T, b: T) -> Option<Self> {
+        Some(Self::from_components(r.try_into().ok()?, g.try_into().ok()?, b.try_into().ok()?, 255))
+    }
+
+    pub fn try_rgba<T: TryInto<u8>>(r: T, g: T, b: T, a: T) -> Option<Self> {
+        Some(Self::from_components(r.try_into().ok()?, g.try_into().ok()?, b.try_into().ok()?, a.try_into().ok()?))
+    }
+
+    pub const fn from_components(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self(r as u32 | (g as u32) << 8 | (b as u32) << 16 | (a as u32) << 24)
+    }
+
+    // "#RGB", "#RGBA", "#RRGGBB" or "#RRGGBBAA", the # is optional
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        // from_str_radix would take a leading + too
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) { return None }
+
+        let channel = |i: usize, width: usize| {
+            let value = u8::from_str_radix(digits.get(i * width..(i + 1) * width)?, 16).ok()?;
+            // A single digit is repeated, F is FF
+            Some(if width == 1 { value * 17 } else { value })
+        };
+
+        match digits.len() {
+            3 | 4 => Some(Self::from_components(channel(0, 1)?, channel(1, 1)?, channel(2, 1)?,
+                if digits.len() == 4 { channel(3, 1)? } else { 255 })),
+            6 | 8 => Some(Self::from_components(channel(0, 2)?, channel(1, 2)?, channel(2, 2)?,
+                if digits.len() == 8 { channel(3, 2)? } else { 255 })),
+            _ => None
+        }
+    }
+
+    // "#RRGGBB", with AA added on when not opaque
+    pub fn to_hex(&self) -> String {
+        let [r, g, b] = self.components();
+        match self.alpha() {
+            255 => format!("#{:02X}{:02X}{:02X}", r, g, b),
+            a => format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+        }
+    }
+
+    // Opaque
+    pub fn from_rgb_u32(rgb: u32) -> Self {
+        Self(rgb & 0xFFFFFF | 0xFF000000)
+    }
+
+    pub fn rgb_u32(&self) -> u32 {
+        self.0 & 0xFFFFFF
+    }
+
+    // [r, g, b]
+    pub fn components(&self) -> [u8; 3] {
+        [self.0 as u8, (self.0 >> 8) as u8, (self.0 >> 16) as u8]
+    }
+
+    pub fn r(&self) -> u8 {
+        self.0 as u8
+    }
+
+    pub fn g(&self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn b(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn alpha(&self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    pub fn with_alpha(&self, alpha: u8) -> Color {
+        Self(self.0 & 0xFFFFFF | (alpha as u32) << 24)
+    }
+
+    // Per channel, alpha included, t = 0 is self and t = 1 is other
+    pub fn lerp(&self, other: Color, t: f64) -> Color {
+        let [r0, g0, b0] = self.components();
+        let [r1, g1, b1] = other.components();
+        let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t.clamp(0.0, 1.0)).round() as u8;
+
+        Color::rgba(mix(r0, r1), mix(g0, g1), mix(b0, b1), mix(self.alpha(), other.alpha()))
+    }
+}
+
+
+// Linear light, for shading math, 0 to 1 is displayable but anything goes in between
+// Color is sRGB encoded, converting with From/Into goes through the sRGB curve,
+// and a Color survives the round trip exactly
+// Alpha is straight (not premultiplied) and never sRGB encoded
+// Arithmetic is on the color channels only, alpha is carried over from the left side
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinearColor {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64
+}
+
+impl LinearColor {
+    pub const BLACK: LinearColor = LinearColor::rgba(0.0, 0.0, 0.0, 1.0);
+    pub const WHITE: LinearColor = LinearColor::rgba(1.0, 1.0, 1.0, 1.0);
+    pub const RED: LinearColor = LinearColor::rgba(1.0, 0.0, 0.0, 1.0);
+    pub const GREEN: LinearColor = LinearColor::rgba(0.0, 1.0, 0.0, 1.0);
+    pub const BLUE: LinearColor = LinearColor::rgba(0.0, 0.0, 1.0, 1.0);
+    pub const TRANSPARENT: LinearColor = LinearColor::rgba(0.0, 0.0, 0.0, 0.0);
+
+    // Opaque
+    pub const fn new(r: f64, g: f64, b: f64) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: f64, g: f64, b: f64, a: f64) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        Color::from_hex(hex).map(Self::from)
+    }
+
+    // All channels, alpha included, t isn't clamped so it can extrapolate
+    pub fn lerp(&self, other: LinearColor, t: f64) -> LinearColor {
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t
+        }
+    }
+
+    // Every channel into 0..1
+    pub fn clamp(&self) -> LinearColor {
+        Self::rgba(self.r.clamp(0.0, 1.0), self.g.clamp(0.0, 1.0), self.b.clamp(0.0, 1.0), self.a.clamp(0.0, 1.0))
+    }
+
+    // Perceived brightness, Rec. 709 weights
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    // HSV and HSL work on the sRGB encoded values, like color pickers do
+    // Hue in degrees, saturation, value and lightness from 0 to 1
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma)
+    }
+
+    // (hue, saturation, value), hue is 0 for grays
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    // (hue, saturation, lightness)
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) };
+        (hue, saturation, lightness)
+    }
+
+    // Opaque, from the chroma and how much gets added to every channel
+    fn from_hue(hue: f64, chroma: f64, offset: f64) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as usize {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x)
+        };
+
+        Self::new(srgb_to_linear(r + offset), srgb_to_linear(g + offset), srgb_to_linear(b + offset))
+    }
+
+    // Hue in degrees plus the largest and smallest sRGB encoded channel
+    fn hue_max_min(&self) -> (f64, f64, f64) {
+        let [r, g, b] = [self.r, self.g, self.b].map(linear_to_srgb);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+
+        (hue, max, min)
+    }
+}
+
+// Any integer to a u8 channel, clamping what doesn't fit
+fn saturate<T: TryInto<u8> + PartialOrd + Default + Copy>(value: T) -> u8 {
+    value.try_into().unwrap_or(if value < T::default() { 0 } else { 255 })
+}
+
+// sRGB encoded 0..1 to linear 0..1
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Linear 0..1 to sRGB encoded 0..1
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl From<Color> for LinearColor {
+    fn from(color: Color) -> Self {
+        let [r, g, b] = color.components().map(|channel| srgb_to_linear(channel as f64 / 255.0));
+        Self::rgba(r, g, b, color.alpha() as f64 / 255.0)
+    }
+}
+
+// Clamped into what a Color can hold
+impl From<LinearColor> for Color {
+    fn from(color: LinearColor) -> Self {
+        let to_u8 = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::from_components(
+            to_u8(linear_to_srgb(color.r)),
+            to_u8(linear_to_srgb(color.g)),
+            to_u8(linear_to_srgb(color.b)),
+            to_u8(color.a)
+        )
+    }
+}
+
+impl Add for LinearColor {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::rgba(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b, self.a)
+    }
+}
+
+impl Sub for LinearColor {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::rgba(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b, self.a)
+    }
+}
+
+// Per channel, eg. light color times surface color
+impl Mul for LinearColor {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::rgba(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b, self.a)
+    }
+}
+
+impl Mul<f64> for LinearColor {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self::rgba(self.r * rhs, self.g * rhs, self.b * rhs, self.a)
+    }
+}
+
+impl Mul<LinearColor> for f64 {
+    type Output = LinearColor;
+
+    fn mul(self, rhs: LinearColor) -> LinearColor {
+        rhs * self
+    }
+}
+
+impl Div<f64> for LinearColor {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        Self::rgba(self.r / rhs, self.g / rhs, self.b / rhs, self.a)
+    }
+}
+
+impl AddAssign for LinearColor {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl MulAssign<f64> for LinearColor {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_invalid_color() {
+        let color = Color::new(256, 34, -1);
+
+        assert_eq!(color, Color::new(255, 34, 0));
+        assert_eq!(Color::rgba(-5, 1000, 2, 300), Color::rgba(0, 255, 2, 255));
+    }
+
+    #[test]
+    fn output_rgb_as_u32() {
+        let color = Color::new(100, 234, 88);
+        let expected = 100_u32 | (234 << 8) | (88 << 16);
+
+        assert_eq!(color.rgb_u32(), expected);
+    }
+
+    #[test]
+    fn color_components_and_lerp() {
+        let color = Color::new(100, 234, 88);
+        assert_eq!(color.components(), [100, 234, 88]);
+        assert_eq!(Color::from_rgb_u32(0xFF00_0000 | color.rgb_u32()), color);
+
+        let black = Color::new(0, 0, 0);
+        assert_eq!(black.lerp(Color::new(255, 100, 10), 0.5), Color::new(128, 50, 5));
+        assert_eq!(black.lerp(color, 2.0), color);
+    }
+
+    #[test]
+    fn color_alpha() {
+        assert_eq!(Color::new(1, 2, 3).alpha(), 255);
+        assert_eq!(Color::from_rgb_u32(0x030201).alpha(), 255);
+
+        let color = Color::rgba(1, 2, 3, 4);
+        assert_eq!(color.alpha(), 4);
+        assert_eq!(color.rgb_u32(), Color::new(1, 2, 3).rgb_u32());
+        assert_eq!(color.with_alpha(255), Color::new(1, 2, 3));
+        assert_eq!(color.lerp(Color::rgba(1, 2, 3, 200), 0.5).alpha(), 102);
+    }
+
+    #[test]
+    fn checked_colors() {
+        assert_eq!(Color::try_new(1, 2, 3), Some(Color::new(1, 2, 3)));
+        assert_eq!(Color::try_new(256, 34, -1), None);
+        assert_eq!(Color::try_rgba(1, 2, 3, 300), None);
+        assert_eq!(Color::rgba(1, 2, 3, 4).b(), 3);
+        assert_eq!([Color::YELLOW.r(), Color::YELLOW.g(), Color::YELLOW.b()], [255, 255, 0]);
+        assert_eq!(Color::TRANSPARENT.alpha(), 0);
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(Color::from_hex("#FF8000"), Some(Color::new(255, 128, 0)));
+        assert_eq!(Color::from_hex("ff800080"), Some(Color::rgba(255, 128, 0, 128)));
+        assert_eq!(Color::from_hex("#F80"), Some(Color::new(255, 136, 0)));
+        assert_eq!(Color::from_hex("#F808"), Some(Color::rgba(255, 136, 0, 136)));
+        for invalid in ["", "#", "#12345", "#GG0000", "+1+2+3", "#ÿÿÿ"] {
+            assert_eq!(Color::from_hex(invalid), None, "{}", invalid);
+        }
+
+        assert_eq!(Color::new(1, 171, 255).to_hex(), "#01ABFF");
+        assert_eq!(Color::rgba(1, 171, 255, 16).to_hex(), "#01ABFF10");
+        assert_eq!(LinearColor::from_hex("#FFFFFF"), Some(LinearColor::WHITE));
+    }
+
+    #[test]
+    fn exact_srgb_round_trip() {
+        for value in 0..=255_u8 {
+            let color = Color::rgba(value, 255 - value, value / 2, value);
+            assert_eq!(Color::from(LinearColor::from(color)), color);
+        }
+
+        // Mid gray in sRGB is much darker in linear light
+        let gray = LinearColor::from(Color::GRAY);
+        assert!((gray.r - 0.2158605).abs() < 1e-6);
+        assert_eq!(Color::from(LinearColor::rgba(2.0, -1.0, 0.5, 1.5)), Color::new(255, 0, 188));
+    }
+
+    #[test]
+    fn linear_arithmetic() {
+        let a = LinearColor::rgba(0.5, 0.25, 1.0, 0.5);
+        let b = LinearColor::new(0.25, 0.5, 0.5);
+
+        assert_eq!(a + b, LinearColor::rgba(0.75, 0.75, 1.5, 0.5));
+        assert_eq!(a - b, LinearColor::rgba(0.25, -0.25, 0.5, 0.5));
+        assert_eq!(a * b, LinearColor::rgba(0.125, 0.125, 0.5, 0.5));
+        assert_eq!(2.0 * a, LinearColor::rgba(1.0, 0.5, 2.0, 0.5));
+        assert_eq!(a / 2.0, LinearColor::rgba(0.25, 0.125, 0.5, 0.5));
+        assert_eq!(a.lerp(b, 0.5), LinearColor::rgba(0.375, 0.375, 0.75, 0.75));
+
+        let mut sum = LinearColor::BLACK;
+        sum += a;
+        sum *= 2.0;
+        assert_eq!(sum, LinearColor::rgba(1.0, 0.5, 2.0, 1.0));
+        assert_eq!(sum.clamp(), LinearColor::rgba(1.0, 0.5, 1.0, 1.0));
+        assert!((LinearColor::WHITE.luminance() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        let orange = LinearColor::from(Color::new(255, 128, 0));
+        let (hue, saturation, value) = orange.to_hsv();
+        assert!((hue - 30.117647).abs() < 1e-6);
+        assert!((saturation - 1.0).abs() < 1e-12 && (value - 1.0).abs() < 1e-12);
+        assert_eq!(Color::from(LinearColor::from_hsv(hue, saturation, value)), Color::new(255, 128, 0));
+
+        let (hue, saturation, lightness) = orange.to_hsl();
+        assert!((saturation - 1.0).abs() < 1e-12);
+        assert!((lightness - 0.5).abs() < 1e-12);
+        assert_eq!(Color::from(LinearColor::from_hsl(hue, saturation, lightness)), Color::new(255, 128, 0));
+
+        assert_eq!(Color::from(LinearColor::from_hsv(240.0, 1.0, 1.0)), Color::BLUE);
+        assert_eq!(Color::from(LinearColor::from_hsl(-240.0, 1.0, 0.5)), Color::GREEN);
+        assert_eq!(LinearColor::from(Color::GRAY).to_hsv().1, 0.0);
+        assert_eq!(LinearColor::BLACK.to_hsl(), (0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,480 @@
+use crate::renderer::{Renderer, Color, Image};
+
+// 2D primitives for HUDs, UI and debug drawing, in framebuffer pixels
+// Everything is clipped to the framebuffer, and shapes entirely off screen cost next to nothing
+// Shapes with subpixel positions sample at pixel centers, so (0.5, 0.5) is the middle of the first pixel
+impl Renderer {
+    // Clipped to the framebuffer, nothing is drawn for a width or height of 0 or less
+    pub fn fill_rect(&mut self, x: isize, y: isize, width: isize, height: isize, color: Color) {
+        let min_x = x.clamp(0, self.width as isize) as usize;
+        let min_y = y.clamp(0, self.height as isize) as usize;
+        let max_x = (x + width).clamp(min_x as isize, self.width as isize) as usize;
+        let max_y = (y + height).clamp(min_y as isize, self.height as isize) as usize;
+
+        for row in min_y..max_y {
+            self.tmp_buffer[(row * self.width + min_x)..(row * self.width + max_x)].fill(color.rgb_u32());
+        }
+    }
+
+    // 1 pixel wide outline, inside the same area fill_rect would cover
+    pub fn draw_rect(&mut self, x: isize, y: isize, width: isize, height: isize, color: Color) {
+        if width <= 0 || height <= 0 { return }
+
+        self.fill_rect(x, y, width, 1, color);
+        self.fill_rect(x, y + height - 1, width, 1, color);
+        self.fill_rect(x, y, 1, height, color);
+        self.fill_rect(x + width - 1, y, 1, height, color);
+    }
+
+    pub fn fill_circle(&mut self, center_x: isize, center_y: isize, radius: isize, color: Color) {
+        self.fill_ellipse(center_x, center_y, radius, radius, color);
+    }
+
+    pub fn draw_circle(&mut self, center_x: isize, center_y: isize, radius: isize, color: Color) {
+        self.draw_ellipse(center_x, center_y, radius, radius, color);
+    }
+
+    // A radius of 0 is a single row or column of pixels
+    pub fn fill_ellipse(&mut self, center_x: isize, center_y: isize, radius_x: isize, radius_y: isize, color: Color) {
+        if radius_x < 0 || radius_y < 0 { return }
+
+        // Only the rows that are on screen
+        let min_dy = (-radius_y).max(-center_y);
+        let max_dy = radius_y.min(self.height as isize - 1 - center_y);
+
+        for dy in min_dy..=max_dy {
+            // The half pixel keeps the outermost rows and columns from being a lone pixel
+            let t = dy as f64 / (radius_y as f64 + 0.5);
+            let half_width = ((radius_x as f64 + 0.5) * (1.0 - t * t).sqrt()) as isize;
+            self.fill_span(center_y + dy, center_x - half_width, center_x + half_width + 1, color);
+        }
+    }
+
+    // Midpoint ellipse algorithm
+    pub fn draw_ellipse(&mut self, center_x: isize, center_y: isize, radius_x: isize, radius_y: isize, color: Color) {
+        if radius_x < 0 || radius_y < 0 { return }
+        // Entirely off screen, don't walk the outline for nothing
+        if center_x + radius_x < 0 || center_y + radius_y < 0
+            || center_x - radius_x >= self.width as isize || center_y - radius_y >= self.height as isize { return }
+        if radius_x == 0 || radius_y == 0 {
+            self.fill_rect(center_x - radius_x, center_y - radius_y, radius_x * 2 + 1, radius_y * 2 + 1, color);
+            return;
+        }
+
+        let plot_4 = |renderer: &mut Renderer, x: isize, y: isize| {
+            for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                renderer.put_pixel(center_x + x * sx, center_y + y * sy, color);
+            }
+        };
+
+        let (rx2, ry2) = ((radius_x * radius_x) as f64, (radius_y * radius_y) as f64);
+        let (mut x, mut y) = (0, radius_y);
+
+        // Where the slope is shallower than -1, step along x
+        let mut decision = ry2 - rx2 * radius_y as f64 + rx2 / 4.0;
+        while ry2 * x as f64 <= rx2 * y as f64 {
+            plot_4(self, x, y);
+            x += 1;
+            if decision < 0.0 {
+                decision += ry2 * (2 * x + 1) as f64;
+            } else {
+                y -= 1;
+                decision += ry2 * (2 * x + 1) as f64 - rx2 * (2 * y) as f64;
+            }
+        }
+
+        // Then along y
+        decision = ry2 * (x as f64 + 0.5).powi(2) + rx2 * ((y - 1) as f64).powi(2) - rx2 * ry2;
+        while y >= 0 {
+            plot_4(self, x, y);
+            y -= 1;
+            if decision > 0.0 {
+                decision += rx2 * (1 - 2 * y) as f64;
+            } else {
+                x += 1;
+                decision += ry2 * (2 * x) as f64 + rx2 * (1 - 2 * y) as f64;
+            }
+        }
+    }
+
+    // Xiaolin Wu's line, each pixel is blended by how much of it the line covers
+    pub fn draw_line_aa(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: Color) {
+        // Every column between the ends is walked, so only keep what's near the screen
+        // The margin leaves the cut ends' partial coverage off screen
+        let Some(((x0, y0), (x1, y1))) = self.clip_line((x0, y0), (x1, y1), 2.0) else { return };
+
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        // Always walk along the long axis, left to right
+        let (mut x0, mut y0, mut x1, mut y1) = if steep { (y0, x0, y1, x1) } else { (x0, y0, x1, y1) };
+        if x0 > x1 {
+            (x0, x1) = (x1, x0);
+            (y0, y1) = (y1, y0);
+        }
+
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+        let plot = |renderer: &mut Renderer, x: isize, y: isize, coverage: f64| {
+            if steep {
+                renderer.blend_pixel(y, x, color, coverage);
+            } else {
+                renderer.blend_pixel(x, y, color, coverage);
+            }
+        };
+
+        // Pixel centers are at .5, so shift to make whole numbers the centers
+        let (x0, y0, x1, y1) = (x0 - 0.5, y0 - 0.5, x1 - 0.5, y1 - 0.5);
+
+        // The end points only partially cover their column
+        let end_point = |renderer: &mut Renderer, x: f64, y: f64, x_gap: f64| -> isize {
+            let x_end = x.round();
+            let y_end = y + gradient * (x_end - x);
+            let pixel_x = x_end as isize;
+            let pixel_y = y_end.floor() as isize;
+            let fraction = y_end - y_end.floor();
+
+            plot(renderer, pixel_x, pixel_y, (1.0 - fraction) * x_gap);
+            plot(renderer, pixel_x, pixel_y + 1, fraction * x_gap);
+            pixel_x
+        };
+
+        let start_x = end_point(self, x0, y0, 1.0 - (x0 + 0.5 - (x0 + 0.5).floor()));
+        let end_x = end_point(self, x1, y1, x1 + 0.5 - (x1 + 0.5).floor());
+
+        let mut y = y0 + gradient * ((start_x as f64 + 1.0) - x0);
+        for x in (start_x + 1)..end_x {
+            let fraction = y - y.floor();
+            plot(self, x, y.floor() as isize, 1.0 - fraction);
+            plot(self, x, y.floor() as isize + 1, fraction);
+            y += gradient;
+        }
+    }
+
+    // Connected line segments, round where they join
+    // Thicknesses of 1 or less are drawn as plain 1 pixel lines
+    pub fn draw_polyline(&mut self, points: &[(f64, f64)], thickness: f64, closed: bool, color: Color) {
+        if points.len() < 2 { return }
+
+        let segment_count = if closed { points.len() } else { points.len() - 1 };
+        let segments = (0..segment_count).map(|i| (points[i], points[(i + 1) % points.len()]));
+
+        if thickness <= 1.0 {
+            for ((x0, y0), (x1, y1)) in segments {
+                self.bresenham_line(color, x0.floor() as isize, y0.floor() as isize, x1.floor() as isize, y1.floor() as isize);
+            }
+            return;
+        }
+
+        let half = thickness / 2.0;
+        for ((x0, y0), (x1, y1)) in segments {
+            let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+            if length == 0.0 { continue }
+
+            let (normal_x, normal_y) = (-(y1 - y0) / length * half, (x1 - x0) / length * half);
+            self.fill_polygon(&[
+                (x0 + normal_x, y0 + normal_y),
+                (x1 + normal_x, y1 + normal_y),
+                (x1 - normal_x, y1 - normal_y),
+                (x0 - normal_x, y0 - normal_y)
+            ], color);
+        }
+
+        let joints = if closed { points } else { &points[1..points.len() - 1] };
+        for &(x, y) in joints {
+            self.fill_disc(x, y, half, color);
+        }
+    }
+
+    // Scanline fill with the even-odd rule, so self intersecting polygons get holes
+    pub fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        if points.len() < 3 { return }
+
+        let min_y = points.iter().map(|&(_, y)| y).fold(f64::INFINITY, f64::min);
+        let max_y = points.iter().map(|&(_, y)| y).fold(f64::NEG_INFINITY, f64::max);
+        let first_row = (min_y - 0.5).ceil().max(0.0) as isize;
+        let last_row = ((max_y - 0.5).ceil() as isize).min(self.height as isize);
+
+        let mut crossings: Vec<f64> = vec![];
+        for row in first_row..last_row {
+            let sample_y = row as f64 + 0.5;
+
+            crossings.clear();
+            for i in 0..points.len() {
+                let (x0, y0) = points[i];
+                let (x1, y1) = points[(i + 1) % points.len()];
+                // Half open, so a vertex shared by two edges only counts once
+                if (y0 <= sample_y) != (y1 <= sample_y) {
+                    crossings.push(x0 + (sample_y - y0) / (y1 - y0) * (x1 - x0));
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+
+            for pair in crossings.chunks_exact(2) {
+                let start = (pair[0] - 0.5).ceil() as isize;
+                let end = (pair[1] - 0.5).ceil() as isize;
+                self.fill_span(row, start, end, color);
+            }
+        }
+    }
+
+    // Alpha blended, opacity (0..1) scales the image's own alpha
+    pub fn blit(&mut self, image: &Image, x: isize, y: isize, opacity: f64) {
+        let min_x = (-x).max(0) as usize;
+        let min_y = (-y).max(0) as usize;
+        let max_x = (self.width as isize - x).clamp(0, image.width() as isize) as usize;
+        let max_y = (self.height as isize - y).clamp(0, image.height() as isize) as usize;
+
+        for image_y in min_y..max_y {
+            for image_x in min_x..max_x {
+                let (color, alpha) = image.pixel(image_x, image_y).unwrap();
+                self.blend_pixel(x + image_x as isize, y + image_y as isize, color, alpha as f64 / 255.0 * opacity);
+            }
+        }
+    }
+
+    // Mixes color over what's already there, alpha 0 leaves it as is and 1 replaces it
+    pub fn blend_pixel(&mut self, x: isize, y: isize, color: Color, alpha: f64) {
+        if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize || alpha <= 0.0 { return }
+
+        let pixel = &mut self.tmp_buffer[x as usize + y as usize * self.width];
+        *pixel = Color::from_rgb_u32(*pixel).lerp(color, alpha).rgb_u32();
+    }
+
+    // Part of the line within margin pixels of the framebuffer, or None if it's all further off
+    // Liang-Barsky, like the debug draw's clipping
+    fn clip_line(&self, start: (f64, f64), end: (f64, f64), margin: f64) -> Option<((f64, f64), (f64, f64))> {
+        let (delta_x, delta_y) = (end.0 - start.0, end.1 - start.1);
+        let (mut t_min, mut t_max) = (0.0_f64, 1.0_f64);
+        let bounds = [
+            (start.0, delta_x, self.width as f64),
+            (start.1, delta_y, self.height as f64)
+        ];
+
+        for (position, delta, size) in bounds {
+            let (min, max) = (-margin, size + margin);
+            if delta == 0.0 {
+                if position < min || position > max { return None }
+                continue;
+            }
+
+            let (t0, t1) = ((min - position) / delta, (max - position) / delta);
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+
+        if t_min > t_max { return None }
+        let at = |t: f64| (start.0 + delta_x * t, start.1 + delta_y * t);
+        Some((at(t_min), at(t_max)))
+    }
+
+    // Like plot_pixel, but negative coordinates are fine too
+    fn put_pixel(&mut self, x: isize, y: isize, color: Color) {
+        if x < 0 || y < 0 { return }
+        self.plot_pixel(x as usize, y as usize, color);
+    }
+
+    // Pixels start_x up to, but not including, end_x on one row
+    fn fill_span(&mut self, y: isize, start_x: isize, end_x: isize, color: Color) {
+        if y < 0 || y >= self.height as isize { return }
+        self.fill_rect(start_x, y, end_x - start_x, 1, color);
+    }
+
+    // Circle with a subpixel center and radius, for the joints of thick lines
+    fn fill_disc(&mut self, center_x: f64, center_y: f64, radius: f64, color: Color) {
+        let first_row = (center_y - radius - 0.5).ceil().max(0.0) as isize;
+        let last_row = ((center_y + radius - 0.5).floor() as isize).min(self.height as isize - 1);
+
+        for row in first_row..=last_row {
+            let dy = row as f64 + 0.5 - center_y;
+            let half_width = (radius * radius - dy * dy).max(0.0).sqrt();
+            let start = (center_x - half_width - 0.5).ceil() as isize;
+            let end = (center_x + half_width - 0.5).floor() as isize + 1;
+            self.fill_span(row, start, end, color);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Color = Color::WHITE;
+
+    fn lit(renderer: &Renderer) -> usize {
+        renderer.buffer().iter().filter(|&&pixel| pixel != 0).count()
+    }
+
+    fn is_lit(renderer: &Renderer, x: usize, y: usize) -> bool {
+        renderer.buffer()[x + y * renderer.width()] != 0
+    }
+
+    #[test]
+    fn rect_outline() {
+        let mut renderer = Renderer::new(10, 10);
+        renderer.draw_rect(1, 1, 4, 3, WHITE);
+
+        assert_eq!(lit(&renderer), 10);
+        assert!(is_lit(&renderer, 4, 3));
+        assert!(!is_lit(&renderer, 2, 2));
+
+        // Half off screen
+        renderer.draw_rect(-2, -2, 4, 4, WHITE);
+        assert!(is_lit(&renderer, 1, 0));
+    }
+
+    #[test]
+    fn circles_are_symmetric() {
+        let mut renderer = Renderer::new(21, 21);
+        renderer.fill_circle(10, 10, 6, WHITE);
+
+        let buffer = renderer.buffer();
+        for y in 0..21 {
+            for x in 0..21 {
+                let pixel = buffer[x + y * 21];
+                assert_eq!(pixel, buffer[(20 - x) + y * 21]);
+                assert_eq!(pixel, buffer[y + x * 21]);
+            }
+        }
+        assert!(is_lit(&renderer, 10, 4) && is_lit(&renderer, 16, 10));
+        assert!(!is_lit(&renderer, 10, 3) && !is_lit(&renderer, 15, 15));
+
+        // Area is roughly pi r^2
+        let area = lit(&renderer) as f64;
+        assert!((area - std::f64::consts::PI * 6.5 * 6.5).abs() < 10.0);
+    }
+
+    #[test]
+    fn ellipse_outline_matches_the_fill() {
+        let mut outline = Renderer::new(30, 20);
+        outline.draw_ellipse(15, 10, 12, 6, WHITE);
+        let mut filled = Renderer::new(30, 20);
+        filled.fill_ellipse(15, 10, 12, 6, WHITE);
+
+        assert!(is_lit(&outline, 3, 10) && is_lit(&outline, 27, 10));
+        assert!(is_lit(&outline, 15, 4) && is_lit(&outline, 15, 16));
+        assert!(!is_lit(&outline, 15, 10));
+
+        // No outline pixel strays more than a pixel from the filled shape
+        for y in 0..20 {
+            for x in 0..30 {
+                if !is_lit(&outline, x, y) { continue }
+                let near_fill = (x.saturating_sub(1)..=x + 1).any(|nx| (y.saturating_sub(1)..=y + 1)
+                    .any(|ny| nx < 30 && ny < 20 && is_lit(&filled, nx, ny)));
+                assert!(near_fill, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_off_screen_are_clipped() {
+        let mut renderer = Renderer::new(8, 8);
+        renderer.fill_circle(-50, 4, 10, WHITE);
+        renderer.draw_circle(100, 100, 10, WHITE);
+        renderer.fill_ellipse(4, -30, 5, 5, WHITE);
+        renderer.draw_line_aa(-20.0, -5.0, -1.0, 50.0, WHITE);
+        renderer.fill_polygon(&[(-10.0, -10.0), (-2.0, -10.0), (-2.0, 20.0)], WHITE);
+        assert_eq!(lit(&renderer), 0);
+
+        // Huge but far away, these would take forever without skipping what's off screen
+        renderer.draw_ellipse(-2_000_000_000, 4, 1_000_000_000, 1_000_000_000, WHITE);
+        renderer.draw_line_aa(-1e15, -1.0, 1e15, -1.0, WHITE);
+        assert_eq!(lit(&renderer), 0);
+
+        // Covering everything
+        renderer.fill_circle(4, 4, 100, WHITE);
+        assert_eq!(lit(&renderer), 64);
+    }
+
+    #[test]
+    fn aa_line_clipped_to_the_screen() {
+        // Cutting off the part past the edge doesn't change what's on screen
+        let mut near = Renderer::new(10, 5);
+        near.draw_line_aa(0.5, 0.5, 19.5, 4.0, WHITE);
+        let mut far = Renderer::new(10, 5);
+        far.draw_line_aa(0.5, 0.5, 0.5 + 19.0 * 1e9, 0.5 + 3.5 * 1e9, WHITE);
+        assert_eq!(near.buffer(), far.buffer());
+        assert!(lit(&far) > 0);
+    }
+
+    #[test]
+    fn aa_line_coverage() {
+        // Horizontal through pixel centers, fully covers one row
+        // except the end points, which stop halfway through their pixel
+        let mut renderer = Renderer::new(10, 5);
+        renderer.draw_line_aa(0.5, 2.5, 9.5, 2.5, WHITE);
+        for x in 1..9 {
+            assert_eq!(renderer.buffer()[x + 2 * 10], WHITE.rgb_u32());
+        }
+        assert_eq!(renderer.buffer()[2 * 10], Color::new(128, 128, 128).rgb_u32());
+        assert_eq!(lit(&renderer), 10);
+
+        // Between two rows, both get half
+        let mut renderer = Renderer::new(10, 5);
+        renderer.draw_line_aa(0.5, 2.0, 9.5, 2.0, WHITE);
+        assert_eq!(renderer.buffer()[5 + 10], Color::new(128, 128, 128).rgb_u32());
+        assert_eq!(renderer.buffer()[5 + 2 * 10], Color::new(128, 128, 128).rgb_u32());
+
+        // Steep lines walk along y
+        let mut renderer = Renderer::new(5, 10);
+        renderer.draw_line_aa(2.5, 0.5, 2.5, 9.5, WHITE);
+        assert_eq!(lit(&renderer), 10);
+    }
+
+    #[test]
+    fn polygon_fill() {
+        let mut renderer = Renderer::new(10, 10);
+        renderer.fill_polygon(&[(1.0, 1.0), (5.0, 1.0), (5.0, 4.0), (1.0, 4.0)], WHITE);
+        assert_eq!(lit(&renderer), 12);
+        assert!(is_lit(&renderer, 1, 1) && is_lit(&renderer, 4, 3));
+        assert!(!is_lit(&renderer, 5, 3));
+
+        // A bow tie crossing over itself
+        let mut renderer = Renderer::new(10, 10);
+        renderer.fill_polygon(&[(0.0, 0.0), (10.0, 10.0), (10.0, 0.0), (0.0, 10.0)], WHITE);
+        assert!(is_lit(&renderer, 1, 5) && is_lit(&renderer, 8, 5));
+        assert!(!is_lit(&renderer, 5, 1) && !is_lit(&renderer, 5, 8));
+    }
+
+    #[test]
+    fn thick_polyline() {
+        let mut renderer = Renderer::new(20, 20);
+        renderer.draw_polyline(&[(2.0, 5.0), (15.0, 5.0), (15.0, 15.0)], 4.0, false, WHITE);
+
+        // 4 pixels wide around y = 5
+        for y in 3..7 {
+            assert!(is_lit(&renderer, 8, y));
+        }
+        assert!(!is_lit(&renderer, 8, 2) && !is_lit(&renderer, 8, 7));
+        // The corner is filled in by the round joint
+        assert!(is_lit(&renderer, 16, 4));
+
+        // Thin lines are plain 1 pixel lines
+        let mut renderer = Renderer::new(20, 20);
+        renderer.draw_polyline(&[(0.0, 0.0), (9.0, 0.0), (9.0, 9.0)], 1.0, true, WHITE);
+        assert!(is_lit(&renderer, 5, 0) && is_lit(&renderer, 9, 5) && is_lit(&renderer, 5, 5));
+    }
+
+    #[test]
+    fn alpha_blit() {
+        let mut renderer = Renderer::new(4, 4);
+        renderer.fill_rect(0, 0, 4, 4, Color::new(0, 0, 200));
+
+        let image = Image::from_rgba(2, 2, vec![
+            [255, 0, 0, 255], [255, 0, 0, 0],
+            [255, 0, 0, 128], [255, 0, 0, 255]
+        ]);
+        renderer.blit(&image, 3, -1, 1.0);
+        renderer.blit(&image, 0, 0, 1.0);
+
+        let pixel = |renderer: &Renderer, x: usize, y: usize| Color::from_rgb_u32(renderer.buffer()[x + y * 4]);
+        assert_eq!(pixel(&renderer, 0, 0), Color::new(255, 0, 0));
+        assert_eq!(pixel(&renderer, 1, 0), Color::new(0, 0, 200));
+        assert_eq!(pixel(&renderer, 0, 1), Color::new(128, 0, 100));
+        // Only the bottom left of the first blit is on screen
+        assert_eq!(pixel(&renderer, 3, 0), Color::new(128, 0, 100));
+
+        renderer.blit(&image, 2, 2, 0.5);
+        assert_eq!(pixel(&renderer, 2, 2), Color::new(128, 0, 100));
+    }
+}
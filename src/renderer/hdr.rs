@@ -0,0 +1,169 @@
+use crate::renderer::{Renderer, Color, LinearColor};
+
+// How HDR values past 1 are squeezed into what the screen can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapping {
+    // Anything past 1 is cut off
+    Clamp,
+    // x / (1 + x), never quite reaches white
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve, more contrast and whiter highlights
+    #[default]
+    Aces
+}
+
+impl ToneMapping {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::Reinhard => "reinhard",
+            Self::Aces => "aces"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Clamp, Self::Reinhard, Self::Aces].into_iter()
+            .find(|mode| mode.name() == name)
+    }
+
+    // One linear channel, 0 and up, to 0..1
+    pub fn apply(&self, value: f64) -> f64 {
+        let value = value.max(0.0);
+        match self {
+            Self::Clamp => value.min(1.0),
+            Self::Reinhard => value / (1.0 + value),
+            Self::Aces => ((value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)).clamp(0.0, 1.0)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrSettings {
+    // In stops, every +1 doubles the brightness
+    pub exposure: f64,
+    pub tone_mapping: ToneMapping
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self { exposure: 0.0, tone_mapping: ToneMapping::default() }
+    }
+}
+
+impl HdrSettings {
+    // The color a linear HDR value ends up as on screen, sRGB encoded
+    pub fn map(&self, color: LinearColor) -> Color {
+        let scale = self.exposure.exp2();
+        let tone_map = |value: f64| self.tone_mapping.apply(value * scale);
+        Color::from(LinearColor::new(tone_map(color.r), tone_map(color.g), tone_map(color.b)))
+    }
+}
+
+// With HDR on, render() writes unclamped linear colors to a float buffer
+// instead of the framebuffer, and resolve() brings them over
+// Everything 2D (text, overlays, debug lines) goes on the framebuffer after resolve()
+impl Renderer {
+    pub fn set_hdr(&mut self, enabled: bool) {
+        if enabled == self.hdr_buffer.is_some() { return }
+
+        self.hdr_buffer = enabled.then(|| vec![LinearColor::BLACK; self.width * self.height]);
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.hdr_buffer.is_some()
+    }
+
+    pub fn hdr_settings(&self) -> &HdrSettings {
+        &self.hdr_settings
+    }
+
+    pub fn hdr_settings_mut(&mut self) -> &mut HdrSettings {
+        &mut self.hdr_settings
+    }
+
+    // None with HDR off
+    pub fn hdr_buffer(&self) -> Option<&[LinearColor]> {
+        self.hdr_buffer.as_deref()
+    }
+
+    // Exposure, tone mapping and sRGB encoding into the framebuffer
+    // Does nothing with HDR off, so it can always be called after render()
+    pub fn resolve(&mut self) {
+        let hdr_buffer = match &self.hdr_buffer {
+            Some(hdr_buffer) => hdr_buffer,
+            None => return
+        };
+
+        for (pixel, &color) in self.tmp_buffer.iter_mut().zip(hdr_buffer.iter()) {
+            *pixel = self.hdr_settings.map(color).rgb_u32();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::BlendMode;
+
+    #[test]
+    fn tone_mapping_curves() {
+        for mode in [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces] {
+            assert_eq!(mode.apply(0.0), 0.0);
+            assert_eq!(mode.apply(-1.0), 0.0);
+            assert!(mode.apply(1000.0) <= 1.0);
+            assert!(mode.apply(0.5) < mode.apply(2.0) || mode == ToneMapping::Clamp);
+            assert_eq!(ToneMapping::from_name(mode.name()), Some(mode));
+        }
+
+        assert_eq!(ToneMapping::Clamp.apply(3.0), 1.0);
+        assert_eq!(ToneMapping::Reinhard.apply(1.0), 0.5);
+        assert_eq!(ToneMapping::Aces.apply(100.0), 1.0);
+    }
+
+    #[test]
+    fn exposure_in_stops() {
+        let settings = HdrSettings { exposure: 1.0, tone_mapping: ToneMapping::Clamp };
+        let quarter = LinearColor::new(0.25, 0.25, 0.25);
+        assert_eq!(settings.map(quarter), Color::from(LinearColor::new(0.5, 0.5, 0.5)));
+
+        // Too bright for the screen either way, but the tone mapper keeps some of it
+        let bright = LinearColor::new(4.0, 1.0, 0.25);
+        let reinhard = HdrSettings { exposure: 0.0, tone_mapping: ToneMapping::Reinhard }.map(bright);
+        assert_eq!(HdrSettings { exposure: 0.0, tone_mapping: ToneMapping::Clamp }.map(bright).r(), 255);
+        assert!(reinhard.r() < 255 && reinhard.r() > reinhard.g());
+    }
+
+    #[test]
+    fn render_through_hdr() {
+        let mut renderer = Renderer::new(10, 10);
+        assert!(!renderer.is_hdr());
+        renderer.set_hdr(true);
+        renderer.hdr_settings_mut().tone_mapping = ToneMapping::Clamp;
+
+        let white = Color::new(255, 255, 255);
+        renderer.draw_triangles(vec![(1, 1), (8, 1), (1, 8)], white);
+        // Nothing reaches the framebuffer before resolving
+        assert!(renderer.buffer().iter().all(|&pixel| pixel == 0));
+
+        renderer.resolve();
+        assert_eq!(renderer.buffer()[2 + 2 * 10], white.rgb_u32());
+        assert_eq!(renderer.hdr_buffer().unwrap()[2 + 2 * 10], LinearColor::WHITE);
+
+        renderer.resize(5, 5);
+        assert_eq!(renderer.hdr_buffer().unwrap().len(), 25);
+        renderer.set_hdr(false);
+        assert!(renderer.hdr_buffer().is_none());
+    }
+
+    #[test]
+    fn linear_blending() {
+        let background = LinearColor::new(1.0, 0.5, 0.0);
+        let source = LinearColor::rgba(3.0, 0.0, 1.0, 0.5);
+
+        assert_eq!(BlendMode::Opaque.blend_linear(source, background), LinearColor::new(3.0, 0.0, 1.0));
+        assert_eq!(BlendMode::Alpha.blend_linear(source, background), LinearColor::new(2.0, 0.25, 0.5));
+        assert_eq!(BlendMode::Additive.blend_linear(source, background), LinearColor::new(2.5, 0.5, 0.5));
+        assert_eq!(BlendMode::Multiply.blend_linear(source, background), LinearColor::new(2.0, 0.25, 0.0));
+    }
+}
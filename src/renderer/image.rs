@@ -0,0 +1,71 @@
+use crate::renderer::Color;
+
+// An RGBA image to blit onto the framebuffer, eg. HUD icons
+// Alpha is 0 for fully transparent, 255 for opaque
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    // Row by row from the top, [r, g, b, a]
+    pixels: Vec<[u8; 4]>
+}
+
+impl Image {
+    // Fully transparent
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0; 4]; width * height]
+        }
+    }
+
+    pub fn from_rgba(width: usize, height: usize, pixels: Vec<[u8; 4]>) -> Self {
+        assert_eq!(pixels.len(), width * height, "Image pixels don't match its size");
+        Self { width, height, pixels }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // None outside the image
+    pub fn pixel(&self, x: usize, y: usize) -> Option<(Color, u8)> {
+        if x >= self.width || y >= self.height { return None }
+
+        let [r, g, b, a] = self.pixels[x + y * self.width];
+        Some((Color::new(r, g, b), a))
+    }
+
+    // Anything outside the image is silently dropped
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color, alpha: u8) {
+        if x >= self.width || y >= self.height { return }
+
+        let [r, g, b] = color.components();
+        self.pixels[x + y * self.width] = [r, g, b, alpha];
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels() {
+        let mut image = Image::new(2, 3);
+        assert_eq!(image.pixel(1, 2), Some((Color::new(0, 0, 0), 0)));
+
+        image.set_pixel(1, 2, Color::new(1, 2, 3), 128);
+        image.set_pixel(2, 0, Color::new(1, 2, 3), 128);
+        assert_eq!(image.pixel(1, 2), Some((Color::new(1, 2, 3), 128)));
+        assert_eq!(image.pixel(2, 0), None);
+
+        let image = Image::from_rgba(1, 2, vec![[1, 2, 3, 4], [5, 6, 7, 8]]);
+        assert_eq!(image.pixel(0, 1), Some((Color::new(5, 6, 7), 8)));
+    }
+}
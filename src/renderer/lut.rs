@@ -0,0 +1,183 @@
+use std::fs;
+use std::io;
+
+// The .cube spec's limit, already 16M entries
+const MAX_SIZE: usize = 256;
+
+// 3D color lookup table for grading, as exported by most grading tools in the .cube format
+// Maps sRGB encoded colors, 0..1 within the domain, to sRGB encoded colors
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut {
+    size: usize,
+    domain_min: [f64; 3],
+    domain_max: [f64; 3],
+    // Red changes fastest, then green, then blue
+    table: Vec<[f64; 3]>
+}
+
+impl Lut {
+    // Leaves every color as it is
+    pub fn identity(size: usize) -> Self {
+        assert!(size >= 2, "A LUT needs at least 2 entries per axis");
+
+        let step = |i: usize| i as f64 / (size - 1) as f64;
+        let table = (0..size * size * size)
+            .map(|i| [step(i % size), step(i / size % size), step(i / (size * size))])
+            .collect();
+
+        Self { size, domain_min: [0.0; 3], domain_max: [1.0; 3], table }
+    }
+
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(file_path)?)
+    }
+
+    // Keywords the engine has no use for (TITLE, LUT_1D_INPUT_RANGE...) are skipped
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut size: Option<usize> = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table: Vec<[f64; 3]> = vec![];
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let error = |message: &str| io::Error::new(
+                io::ErrorKind::InvalidData, format!("Line {}: {}", line_number + 1, message)
+            );
+            let number = |token: &str| token.parse::<f64>().map_err(|_| error(&format!("Invalid number '{}'", token)));
+            let triple = |r: &str, g: &str, b: &str| -> io::Result<[f64; 3]> { Ok([number(r)?, number(g)?, number(b)?]) };
+
+            let tokens = line.split_whitespace().collect::<Vec<&str>>();
+            match tokens.as_slice() {
+                ["LUT_3D_SIZE", value] => {
+                    let value = value.parse::<usize>().ok().filter(|value| (2..=MAX_SIZE).contains(value))
+                        .ok_or_else(|| error(&format!("Invalid size '{}'", value)))?;
+                    size = Some(value);
+                },
+                ["LUT_1D_SIZE", ..] => return Err(error("1D LUTs aren't supported")),
+                ["DOMAIN_MIN", r, g, b] => domain_min = triple(r, g, b)?,
+                ["DOMAIN_MAX", r, g, b] => domain_max = triple(r, g, b)?,
+                [r, g, b] if r.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                    if size.is_none() { return Err(error("Expected LUT_3D_SIZE before the table")) }
+                    table.push(triple(r, g, b)?);
+                },
+                [keyword, ..] if keyword.starts_with(|c: char| c.is_ascii_uppercase()) => (),
+                _ => return Err(error("Expected 3 numbers"))
+            }
+        }
+
+        let size = size.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No LUT_3D_SIZE found."))?;
+        let entries = size.checked_mul(size).and_then(|square| square.checked_mul(size))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "LUT too big."))?;
+        if table.len() != entries {
+            let message = format!("Expected {} entries, found {}.", entries, table.len());
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Empty domain."));
+        }
+
+        Ok(Self { size, domain_min, domain_max, table })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Trilinear between the 8 closest entries, anything outside the domain is clamped to it
+    pub fn sample(&self, color: [f64; 3]) -> [f64; 3] {
+        let last = (self.size - 1) as f64;
+        let position: [f64; 3] = std::array::from_fn(|i| {
+            let t = (color[i] - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i]);
+            t.clamp(0.0, 1.0) * last
+        });
+        let low = position.map(|p| (p.floor() as usize).min(self.size - 2));
+        let fraction: [f64; 3] = std::array::from_fn(|i| position[i] - low[i] as f64);
+
+        let entry = |r: usize, g: usize, b: usize| self.table[r + g * self.size + b * self.size * self.size];
+        let mut result = [0.0; 3];
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight = (0..3)
+                .map(|i| if offset[i] == 1 { fraction[i] } else { 1.0 - fraction[i] })
+                .product::<f64>();
+            if weight == 0.0 { continue }
+
+            let value = entry(low[0] + offset[0], low[1] + offset[1], low[2] + offset[2]);
+            for i in 0..3 {
+                result[i] += value[i] * weight;
+            }
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-9), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn identity_samples() {
+        let lut = Lut::identity(4);
+        for color in [[0.0, 0.0, 0.0], [0.2, 0.5, 0.9], [1.0, 1.0, 1.0]] {
+            assert_close(lut.sample(color), color);
+        }
+        assert_close(lut.sample([-1.0, 2.0, 0.5]), [0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn parse_cube() {
+        // Inverts red, keeps the rest
+        let text = "\
+TITLE \"Invert red\"
+# Comment
+LUT_3D_SIZE 2
+
+1 0 0
+0 0 0
+1 1 0
+0 1 0
+1 0 1
+0 0 1
+1 1 1
+0 1 1
+";
+        let lut = Lut::parse(text).unwrap();
+        assert_eq!(lut.size(), 2);
+        assert_close(lut.sample([0.25, 0.5, 0.75]), [0.75, 0.5, 0.75]);
+
+        let halved = text.replace("LUT_3D_SIZE 2", "LUT_3D_SIZE 2\nDOMAIN_MAX 2 2 2");
+        assert_close(Lut::parse(&halved).unwrap().sample([0.5, 1.0, 2.0]), [0.75, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = Lut::parse("LUT_3D_SIZE 2\n0 0 0\n").unwrap_err();
+        assert_eq!(error.to_string(), "Expected 8 entries, found 1.");
+
+        let error = Lut::parse("0 0 0\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Expected LUT_3D_SIZE before the table");
+
+        let error = Lut::parse("LUT_3D_SIZE 2\n0 0 x\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Invalid number 'x'");
+
+        let error = Lut::parse("LUT_3D_SIZE 1\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Invalid size '1'");
+
+        let error = Lut::parse("LUT_3D_SIZE 3000000\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Invalid size '3000000'");
+
+        let error = Lut::parse("LUT_1D_SIZE 16\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: 1D LUTs aren't supported");
+
+        let error = Lut::parse("TITLE \"Empty\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "No LUT_3D_SIZE found.");
+    }
+}
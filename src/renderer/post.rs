@@ -0,0 +1,418 @@
+use std::fs;
+use std::io;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::renderer::{Renderer, Color, LinearColor, Lut};
+use crate::renderer::color::{srgb_to_linear, linear_to_srgb};
+
+// Blurs reach at most this many pixels out, past it they'd take forever
+// (and a huge kernel) without looking any different on a normal sized frame
+pub const MAX_BLUR_RADIUS: usize = 256;
+// A gaussian reaches out 3 sigmas
+pub const MAX_SIGMA: f64 = MAX_BLUR_RADIUS as f64 / 3.0;
+
+// One pass over the finished frame, they run in the order given to the renderer
+// Blurring and blooming happen in linear light, distances are in pixels
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostEffect {
+    // Each pixel becomes the average of the (2 * radius + 1)^2 square around it
+    // Radius and sigmas are capped at MAX_BLUR_RADIUS and MAX_SIGMA
+    BoxBlur { radius: usize },
+    GaussianBlur { sigma: f64 },
+    // Whatever has a luminance over threshold glows, spread by sigma
+    Bloom { threshold: f64, intensity: f64, sigma: f64 },
+    // Darkens towards the corners, from radius (0 is the center, 1 a corner) on
+    // strength 1 makes the corners black
+    Vignette { strength: f64, radius: f64 },
+    // Red is pushed out and blue pulled in, by up to strength pixels in the corners
+    ChromaticAberration { strength: f64 },
+    // Strength 0 leaves colors alone, 1 fully graded
+    ColorGrading { lut: Lut, strength: f64 }
+}
+
+// Post effect stacks are plain text, one effect per line, in order:
+//
+//   box_blur <radius>
+//   gaussian_blur <sigma>
+//   bloom <threshold> <intensity> <sigma>
+//   vignette <strength> <radius>
+//   chromatic_aberration <strength>
+//   color_grading <path to .cube> [<strength>]
+pub fn load(file_path: &str) -> io::Result<Vec<PostEffect>> {
+    parse(&fs::read_to_string(file_path)?)
+}
+
+pub fn parse(text: &str) -> io::Result<Vec<PostEffect>> {
+    let mut effects = vec![];
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue }
+
+        let error = |message: &str| io::Error::new(
+            io::ErrorKind::InvalidData, format!("Line {}: {}", line_number + 1, message)
+        );
+        let number = |token: &str| token.parse::<f64>().map_err(|_| error(&format!("Invalid number '{}'", token)));
+        let sigma = |token: &str| number(token).and_then(|sigma| match (0.0..=MAX_SIGMA).contains(&sigma) {
+            true => Ok(sigma),
+            false => Err(error(&format!("Invalid sigma '{}', expected 0 to {:.1}", token, MAX_SIGMA)))
+        });
+
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        let effect = match tokens.as_slice() {
+            ["box_blur", radius] => PostEffect::BoxBlur {
+                radius: radius.parse().ok().filter(|&radius| radius <= MAX_BLUR_RADIUS).ok_or_else(|| {
+                    error(&format!("Invalid radius '{}', expected 0 to {}", radius, MAX_BLUR_RADIUS))
+                })?
+            },
+            ["gaussian_blur", value] => PostEffect::GaussianBlur { sigma: sigma(value)? },
+            ["bloom", threshold, intensity, value] => PostEffect::Bloom {
+                threshold: number(threshold)?,
+                intensity: number(intensity)?,
+                sigma: sigma(value)?
+            },
+            ["vignette", strength, radius] => PostEffect::Vignette { strength: number(strength)?, radius: number(radius)? },
+            ["chromatic_aberration", strength] => PostEffect::ChromaticAberration { strength: number(strength)? },
+            ["color_grading", path, rest @ ..] if rest.len() <= 1 => PostEffect::ColorGrading {
+                lut: Lut::load(path).map_err(|lut_error| error(&format!("{}: {}", path, lut_error)))?,
+                strength: rest.first().map_or(Ok(1.0), |strength| number(strength))?
+            },
+            [keyword, ..] => return Err(error(&format!("Unknown or malformed effect '{}'", keyword))),
+            [] => unreachable!()
+        };
+        effects.push(effect);
+    }
+
+    Ok(effects)
+}
+
+impl Renderer {
+    pub fn post_effects(&self) -> &[PostEffect] {
+        &self.post_effects
+    }
+
+    pub fn post_effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.post_effects
+    }
+
+    // Runs every effect over the framebuffer, call it after render() (and resolve() with HDR on)
+    // but before drawing anything that shouldn't be blurred, like text
+    pub fn post_process(&mut self) {
+        if self.post_effects.is_empty() { return }
+        let post_start = Instant::now();
+
+        let decode = srgb_table();
+        let mut frame = Frame {
+            width: self.width,
+            height: self.height,
+            pixels: self.tmp_buffer.iter().map(|&pixel| {
+                let [r, g, b] = Color::from_rgb_u32(pixel).components().map(|channel| decode[channel as usize]);
+                LinearColor::new(r, g, b)
+            }).collect()
+        };
+
+        for effect in self.post_effects.iter() {
+            frame.apply(effect);
+        }
+
+        for (pixel, color) in self.tmp_buffer.iter_mut().zip(frame.pixels) {
+            *pixel = Color::from(color).rgb_u32();
+        }
+        self.stats.post_time = post_start.elapsed();
+    }
+}
+
+// Linear value of every sRGB encoded byte, saves a powf per channel per pixel
+fn srgb_table() -> &'static [f64; 256] {
+    static TABLE: OnceLock<[f64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|i| srgb_to_linear(i as f64 / 255.0)))
+}
+
+// The framebuffer in linear light while effects run
+struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<LinearColor>
+}
+
+impl Frame {
+    // Coordinates past the edges are clamped to them
+    fn get(&self, x: isize, y: isize) -> LinearColor {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[x + y * self.width]
+    }
+
+    fn apply(&mut self, effect: &PostEffect) {
+        if self.pixels.is_empty() { return }
+
+        match effect {
+            PostEffect::BoxBlur { radius } => {
+                let radius = (*radius).min(MAX_BLUR_RADIUS);
+                let kernel = vec![1.0 / (2 * radius + 1) as f64; 2 * radius + 1];
+                self.convolve(&kernel);
+            },
+            PostEffect::GaussianBlur { sigma } => self.convolve(&gaussian_kernel(*sigma)),
+            PostEffect::Bloom { threshold, intensity, sigma } => self.bloom(*threshold, *intensity, *sigma),
+            PostEffect::Vignette { strength, radius } => self.vignette(*strength, *radius),
+            PostEffect::ChromaticAberration { strength } => self.chromatic_aberration(*strength),
+            PostEffect::ColorGrading { lut, strength } => {
+                for pixel in self.pixels.iter_mut() {
+                    let encoded = [pixel.r, pixel.g, pixel.b].map(|channel| linear_to_srgb(channel.clamp(0.0, 1.0)));
+                    let [r, g, b] = lut.sample(encoded).map(srgb_to_linear);
+                    *pixel = pixel.lerp(LinearColor::new(r, g, b), *strength);
+                }
+            }
+        }
+    }
+
+    // Separable, kernel has an odd length and is centered on the pixel
+    fn convolve(&mut self, kernel: &[f64]) {
+        let radius = (kernel.len() / 2) as isize;
+        let pass = |frame: &Frame, horizontal: bool| {
+            let mut pixels = Vec::with_capacity(frame.pixels.len());
+            for y in 0..frame.height as isize {
+                for x in 0..frame.width as isize {
+                    let mut sum = LinearColor::BLACK;
+                    for (i, &weight) in kernel.iter().enumerate() {
+                        let offset = i as isize - radius;
+                        let sample = if horizontal { frame.get(x + offset, y) } else { frame.get(x, y + offset) };
+                        sum += sample * weight;
+                    }
+                    pixels.push(sum);
+                }
+            }
+            pixels
+        };
+
+        self.pixels = pass(self, true);
+        self.pixels = pass(self, false);
+    }
+
+    fn bloom(&mut self, threshold: f64, intensity: f64, sigma: f64) {
+        // Only the part of each pixel over the threshold spills out
+        let mut bright = Frame {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&pixel| {
+                let luminance = pixel.luminance();
+                if luminance <= threshold { return LinearColor::BLACK }
+                pixel * ((luminance - threshold) / luminance)
+            }).collect()
+        };
+        bright.convolve(&gaussian_kernel(sigma));
+
+        for (pixel, glow) in self.pixels.iter_mut().zip(bright.pixels) {
+            *pixel += glow * intensity;
+        }
+    }
+
+    fn vignette(&mut self, strength: f64, radius: f64) {
+        let (center_x, center_y) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        let half_diagonal = center_x.hypot(center_y);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // Distance from the center of the pixel, 1 in the corners
+                let distance = (x as f64 + 0.5 - center_x).hypot(y as f64 + 0.5 - center_y) / half_diagonal;
+                let t = ((distance - radius) / (1.0 - radius).max(f64::EPSILON)).clamp(0.0, 1.0);
+                let smooth = t * t * (3.0 - 2.0 * t);
+                self.pixels[x + y * self.width] *= 1.0 - strength * smooth;
+            }
+        }
+    }
+
+    fn chromatic_aberration(&mut self, strength: f64) {
+        let (center_x, center_y) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        let half_diagonal = center_x.hypot(center_y);
+        let source = Frame { width: self.width, height: self.height, pixels: self.pixels.clone() };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // Grows linearly from nothing in the center to strength in the corners
+                let (dx, dy) = (x as f64 + 0.5 - center_x, y as f64 + 0.5 - center_y);
+                let (shift_x, shift_y) = (dx / half_diagonal * strength, dy / half_diagonal * strength);
+                let sample = |direction: f64| source.get(
+                    (x as f64 - shift_x * direction).round() as isize,
+                    (y as f64 - shift_y * direction).round() as isize
+                );
+
+                let pixel = &mut self.pixels[x + y * self.width];
+                pixel.r = sample(1.0).r;
+                pixel.b = sample(-1.0).b;
+            }
+        }
+    }
+}
+
+// Normalized, out to 3 sigmas where it's practically 0
+fn gaussian_kernel(sigma: f64) -> Vec<f64> {
+    if sigma.is_nan() || sigma <= 0.0 { return vec![1.0] }
+    let sigma = sigma.min(MAX_SIGMA);
+
+    let radius = (sigma * 3.0).ceil() as isize;
+    let weights = (-radius..=radius)
+        .map(|offset| (-(offset * offset) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<f64>>();
+    let total = weights.iter().sum::<f64>();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn renderer_with_dot(width: usize, height: usize, x: usize, y: usize, color: Color) -> Renderer {
+        let mut renderer = Renderer::new(width, height);
+        renderer.plot_pixel(x, y, color);
+        renderer
+    }
+
+    fn pixel(renderer: &Renderer, x: usize, y: usize) -> Color {
+        Color::from_rgb_u32(renderer.buffer()[x + y * renderer.width()])
+    }
+
+    #[test]
+    fn no_effects_leave_the_frame_alone() {
+        let mut renderer = renderer_with_dot(4, 4, 1, 2, Color::new(10, 200, 30));
+        let before = renderer.buffer().clone();
+        renderer.post_process();
+        assert_eq!(renderer.buffer(), &before);
+
+        // Converting to linear and back is exact too
+        renderer.post_effects_mut().push(PostEffect::BoxBlur { radius: 0 });
+        renderer.post_process();
+        assert_eq!(renderer.buffer(), &before);
+    }
+
+    #[test]
+    fn blurs_spread_light() {
+        for effect in [PostEffect::BoxBlur { radius: 1 }, PostEffect::GaussianBlur { sigma: 1.0 }] {
+            let mut renderer = renderer_with_dot(9, 9, 4, 4, Color::WHITE);
+            renderer.post_effects_mut().push(effect);
+            renderer.post_process();
+
+            let center = pixel(&renderer, 4, 4).r();
+            let next_to = pixel(&renderer, 5, 4).r();
+            assert!(center < 255 && next_to > 0 && next_to <= center);
+            // Symmetric
+            assert_eq!(pixel(&renderer, 3, 4), pixel(&renderer, 5, 4));
+            assert_eq!(pixel(&renderer, 4, 3), pixel(&renderer, 4, 5));
+            assert_eq!(pixel(&renderer, 0, 0).r(), 0);
+        }
+
+        let kernel = gaussian_kernel(2.0);
+        assert_eq!(kernel.len(), 13);
+        assert!((kernel.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bloom_only_from_bright_pixels() {
+        let mut renderer = renderer_with_dot(9, 9, 2, 4, Color::WHITE);
+        renderer.plot_pixel(6_usize, 4, Color::new(60, 60, 60));
+        renderer.post_effects_mut().push(PostEffect::Bloom { threshold: 0.5, intensity: 1.0, sigma: 1.0 });
+        renderer.post_process();
+
+        // The white dot glows, the gray one doesn't and stays as it was
+        assert!(pixel(&renderer, 3, 4).r() > 0);
+        assert_eq!(pixel(&renderer, 2, 4), Color::WHITE);
+        assert_eq!(pixel(&renderer, 7, 4).r(), 0);
+        assert_eq!(pixel(&renderer, 6, 4), Color::new(60, 60, 60));
+    }
+
+    #[test]
+    fn vignette_darkens_corners() {
+        let mut renderer = Renderer::new(20, 20);
+        renderer.fill_rect(0, 0, 20, 20, Color::WHITE);
+        renderer.post_effects_mut().push(PostEffect::Vignette { strength: 1.0, radius: 0.5 });
+        renderer.post_process();
+
+        assert_eq!(pixel(&renderer, 10, 10), Color::WHITE);
+        // The corner pixel's center is just short of the corner, 2.8% of the light is left
+        assert!(pixel(&renderer, 0, 0).r() < 50);
+        assert!(pixel(&renderer, 0, 10).r() > pixel(&renderer, 0, 0).r());
+    }
+
+    #[test]
+    fn chromatic_aberration_splits_channels() {
+        let mut renderer = renderer_with_dot(21, 21, 16, 10, Color::WHITE);
+        renderer.post_effects_mut().push(PostEffect::ChromaticAberration { strength: 4.0 });
+        renderer.post_process();
+
+        // Red lands further out, blue further in, green stays
+        let red = (0..21).find(|&x| pixel(&renderer, x, 10).r() > 0).unwrap();
+        let blue = (0..21).find(|&x| pixel(&renderer, x, 10).b() > 0).unwrap();
+        assert!(red > 16 && blue < 16);
+        assert_eq!(pixel(&renderer, 16, 10).g(), 255);
+    }
+
+    #[test]
+    fn color_grading() {
+        let mut renderer = renderer_with_dot(2, 1, 0, 0, Color::new(200, 100, 50));
+        renderer.post_effects_mut().push(PostEffect::ColorGrading { lut: Lut::identity(5), strength: 1.0 });
+        renderer.post_process();
+        assert_eq!(pixel(&renderer, 0, 0), Color::new(200, 100, 50));
+
+        // Swaps red and blue
+        let mut swap = String::from("LUT_3D_SIZE 2\n");
+        for i in 0..8 {
+            swap += &format!("{} {} {}\n", (i >> 2) & 1, (i >> 1) & 1, i & 1);
+        }
+        renderer.post_effects_mut()[0] = PostEffect::ColorGrading { lut: Lut::parse(&swap).unwrap(), strength: 1.0 };
+        renderer.post_process();
+        assert_eq!(pixel(&renderer, 0, 0), Color::new(50, 100, 200));
+    }
+
+    #[test]
+    fn parse_stack() {
+        let lut_path = env::temp_dir()
+            .join(format!("zesty_{}_identity.cube", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let mut cube = String::from("LUT_3D_SIZE 2\n");
+        for i in 0..8 {
+            cube += &format!("{} {} {}\n", i & 1, (i >> 1) & 1, (i >> 2) & 1);
+        }
+        fs::write(&lut_path, cube).unwrap();
+
+        let text = format!("# Stack\nbloom 0.8 1.5 2\nvignette 0.5 0.6\ncolor_grading {} 0.5\nbox_blur 1\n", lut_path);
+        let effects = parse(&text).unwrap();
+        assert_eq!(effects, [
+            PostEffect::Bloom { threshold: 0.8, intensity: 1.5, sigma: 2.0 },
+            PostEffect::Vignette { strength: 0.5, radius: 0.6 },
+            PostEffect::ColorGrading { lut: Lut::identity(2), strength: 0.5 },
+            PostEffect::BoxBlur { radius: 1 }
+        ]);
+        fs::remove_file(&lut_path).unwrap();
+
+        let error = parse("gaussian_blur x\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Invalid number 'x'");
+        let error = parse("\nsharpen 1\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Unknown or malformed effect 'sharpen'");
+        let error = parse(&format!("color_grading {}\n", lut_path)).unwrap_err();
+        assert!(error.to_string().starts_with("Line 1: "));
+
+        // Sizes that would overflow or take forever are turned away
+        let error = parse("box_blur 100000\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Invalid radius '100000', expected 0 to 256");
+        let error = parse("box_blur 99999999999999999999999\n").unwrap_err();
+        assert_eq!(error.to_string(), "Line 1: Invalid radius '99999999999999999999999', expected 0 to 256");
+        for sigma in ["1e300", "inf", "NaN", "-1"] {
+            let error = parse(&format!("bloom 0.5 1 {}\n", sigma)).unwrap_err();
+            assert_eq!(error.to_string(), format!("Line 1: Invalid sigma '{}', expected 0 to 85.3", sigma));
+        }
+    }
+
+    #[test]
+    fn oversized_blurs_are_capped() {
+        // Built in code rather than parsed, so nothing turned them away
+        let mut renderer = renderer_with_dot(5, 5, 2, 2, Color::WHITE);
+        renderer.post_effects_mut().push(PostEffect::BoxBlur { radius: usize::MAX });
+        renderer.post_effects_mut().push(PostEffect::GaussianBlur { sigma: f64::INFINITY });
+        renderer.post_process();
+        assert!(pixel(&renderer, 2, 2).r() < 255);
+    }
+}
@@ -1,7 +1,409 @@
-use crate::object::{Object, Camera};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::object::{Object, Camera, AspectRatio, ProjectionMode};
+use crate::transform::Transform;
+use crate::component::mesh::Mesh;
+use crate::component::material::Material;
+use crate::renderer::{Color, BlendMode};
+use crate::math_utils::{vector3d::Vector3D, quaternion::Quaternion, ray::Ray};
 
 #[derive(Debug)]
 pub struct Scene {
     pub objects: Vec<Object>,
     pub camera: Camera
 }
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RaycastHit {
+    pub object_index: usize,
+    pub triangle_index: usize,
+    pub barycentric: [f64; 3],
+    pub distance: f64,
+    pub point: Vector3D
+}
+
+// Scene files are plain text, one statement per line, in the spirit of OBJ:
+//
+//   camera <near> <far> <fov> <aspect w> <aspect h>
+//   orthographic <view size>
+//   object
+//   position <x> <y> <z>
+//   rotation <w> <x> <y> <z>
+//   scale <x> <y> <z>
+//   mesh <path to .obj>
+//   material <opaque|alpha|additive|multiply> <r> <g> <b> [<a> [<intensity>]]
+//
+// Colors are 0 to 255, alpha defaults to 255 and intensity to 1
+// position/rotation/scale/mesh/material apply to the last camera or object declared
+// orthographic is optional, and switches the camera out of perspective mode
+impl Scene {
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let file = File::open(file_path)?;
+        let lines = BufReader::new(file).lines();
+
+        let mut camera: Option<Camera> = None;
+        let mut objects: Vec<Object> = vec![];
+        // The entity the transform lines are currently talking about
+        let mut current: Option<Entity> = None;
+
+        for (line_number, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let error = |message: &str| invalid_data(line_number + 1, message);
+
+            match keyword {
+                "camera" => {
+                    let [n, f, fov, w, h] = parse_numbers::<5>(rest).ok_or_else(|| error("Expected 5 numbers"))?;
+                    camera = Some(Camera::new(n, f, fov, AspectRatio(w, h)));
+                    current = Some(Entity::Camera);
+                },
+                "orthographic" => {
+                    let camera = camera.as_mut().ok_or_else(|| error("Orthographic given before the camera"))?;
+                    let [view_size] = parse_numbers::<1>(rest).ok_or_else(|| error("Expected 1 number"))?;
+                    camera.set_view_size(view_size);
+                    camera.set_projection_mode(ProjectionMode::Orthographic);
+                },
+                "object" => {
+                    objects.push(Object::new());
+                    current = Some(Entity::Object);
+                },
+                "position" | "rotation" | "scale" => {
+                    let transform = match current {
+                        Some(Entity::Camera) => &mut camera.as_mut().unwrap().transform,
+                        Some(Entity::Object) => &mut objects.last_mut().unwrap().transform,
+                        None => return Err(error("Transform given before any camera or object"))
+                    };
+
+                    match keyword {
+                        "position" => {
+                            let [x, y, z] = parse_numbers::<3>(rest).ok_or_else(|| error("Expected 3 numbers"))?;
+                            transform.set_position(Vector3D::new(x, y, z));
+                        },
+                        "rotation" => {
+                            let [w, x, y, z] = parse_numbers::<4>(rest).ok_or_else(|| error("Expected 4 numbers"))?;
+                            transform.set_rotation(Quaternion::new(w, x, y, z));
+                        },
+                        _ => {
+                            let [x, y, z] = parse_numbers::<3>(rest).ok_or_else(|| error("Expected 3 numbers"))?;
+                            transform.set_scale(Vector3D::new(x, y, z));
+                        }
+                    }
+                },
+                "mesh" => {
+                    let object = match current {
+                        Some(Entity::Object) => objects.last_mut().unwrap(),
+                        _ => return Err(error("Mesh given outside of an object"))
+                    };
+
+                    let mut mesh = Mesh::new();
+                    mesh.load_obj(rest)?;
+                    object.add_component(mesh);
+                },
+                "material" => {
+                    let object = match current {
+                        Some(Entity::Object) => objects.last_mut().unwrap(),
+                        _ => return Err(error("Material given outside of an object"))
+                    };
+
+                    let (mode, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    let blend_mode = BlendMode::from_name(mode)
+                        .ok_or_else(|| error(&format!("Unknown blend mode '{}'", mode)))?;
+                    let tokens = rest.split_whitespace().collect::<Vec<&str>>();
+                    let (color, intensity) = tokens.split_at(tokens.len().min(4));
+                    let color = parse_color(&color.join(" ")).ok_or_else(|| error("Expected 3 or 4 numbers from 0 to 255"))?;
+
+                    let mut material = Material::transparent(color, blend_mode);
+                    if !intensity.is_empty() {
+                        [material.intensity] = parse_numbers::<1>(&intensity.join(" "))
+                            .ok_or_else(|| error("Expected a single intensity after the alpha"))?;
+                    }
+                    object.add_component(material);
+                },
+                _ => return Err(error(&format!("Unknown statement '{}'", keyword)))
+            }
+        }
+
+        let camera = camera.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No camera found."))?;
+        Ok(Self { objects, camera })
+    }
+
+    // Closest mesh hit by a world space ray, eg. from Camera::pixel_to_ray
+    pub fn raycast(&self, ray: &Ray) -> Option<RaycastHit> {
+        let mut closest: Option<RaycastHit> = None;
+
+        for (object_index, object) in self.objects.iter().enumerate() {
+            // Test in object space, so the vertices don't need transforming
+            let ray_in_object = ray.transform(&object.transform.inverse_matrix());
+            let mesh = match object.get_component::<Mesh>() {
+                Some(mesh) => mesh,
+                None => continue
+            };
+
+            let (triangle_index, hit) = match mesh.raycast(&ray_in_object) {
+                Some(hit) => hit,
+                None => continue
+            };
+
+            if closest.is_none_or(|closest_hit| hit.distance < closest_hit.distance) {
+                closest = Some(RaycastHit {
+                    object_index,
+                    triangle_index,
+                    barycentric: hit.barycentric,
+                    distance: hit.distance,
+                    point: ray.at(hit.distance)
+                });
+            }
+        }
+
+        closest
+    }
+
+    // Called once a frame is done, so Transform::has_changed()
+    // only reports what changed during the current frame
+    pub fn end_frame(&mut self) {
+        self.camera.transform.clear_changed();
+        for object in self.objects.iter_mut() {
+            object.transform.clear_changed();
+        }
+    }
+
+    pub fn save(&self, file_path: &str) -> io::Result<()> {
+        let mut file = File::create(file_path)?;
+
+        let AspectRatio(w, h) = self.camera.aspect_ratio();
+        writeln!(file, "camera {} {} {} {} {}",
+            self.camera.near_clip_distance(),
+            self.camera.far_clip_distance(),
+            self.camera.field_of_view(),
+            w, h
+        )?;
+        if self.camera.projection_mode() == ProjectionMode::Orthographic {
+            writeln!(file, "orthographic {}", self.camera.view_size())?;
+        }
+        write_transform(&mut file, &self.camera.transform)?;
+
+        for object in self.objects.iter() {
+            writeln!(file)?;
+            writeln!(file, "object")?;
+            write_transform(&mut file, &object.transform)?;
+
+            if let Some(mesh) = object.get_component::<Mesh>() {
+                writeln!(file, "mesh {}", mesh.src())?;
+            }
+            if let Some(material) = object.get_component::<Material>() {
+                let [r, g, b] = material.color.components();
+                writeln!(file, "material {} {} {} {} {} {}",
+                    material.blend_mode.name(), r, g, b, material.color.alpha(), material.intensity)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Entity {
+    Camera,
+    Object
+}
+
+fn write_transform(file: &mut File, transform: &Transform) -> io::Result<()> {
+    let position = transform.position();
+    let [w, x, y, z] = transform.rotation().components();
+    let scale = transform.scale();
+
+    writeln!(file, "position {} {} {}", position.x, position.y, position.z)?;
+    writeln!(file, "rotation {} {} {} {}", w, x, y, z)?;
+    writeln!(file, "scale {} {} {}", scale.x, scale.y, scale.z)
+}
+
+fn parse_numbers<const N: usize>(text: &str) -> Option<[f64; N]> {
+    let mut numbers = [0.0; N];
+    let mut tokens = text.split_whitespace();
+
+    for number in numbers.iter_mut() {
+        *number = tokens.next()?.parse::<f64>().ok()?;
+    }
+
+    // Trailing garbage is as wrong as missing numbers
+    if tokens.next().is_some() { return None }
+    Some(numbers)
+}
+
+fn parse_color(text: &str) -> Option<Color> {
+    let channels = text.split_whitespace()
+        .map(|token| token.parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(Color::new(r, g, b)),
+        [r, g, b, a] => Some(Color::rgba(r, g, b, a)),
+        _ => None
+    }
+}
+
+fn invalid_data(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {}", line_number, message))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::Space;
+    use std::env;
+    use std::fs;
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("zesty_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn save_and_load_scene() {
+        let obj_path = temp_path("triangle.obj");
+        fs::write(&obj_path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+
+        let mut with_mesh = Object::new();
+        with_mesh.transform = Transform::from_trs(
+            Vector3D::new(1, 2, 3),
+            Quaternion::from_euler_angles(0.5, 0.25, 0.0),
+            Vector3D::new(1, 2, 1)
+        );
+        let mut mesh = Mesh::new();
+        mesh.load_obj(&obj_path).unwrap();
+        with_mesh.add_component(mesh);
+        let mut material = Material::transparent(Color::rgba(10, 20, 30, 40), BlendMode::Additive);
+        material.intensity = 2.5;
+        with_mesh.add_component(material);
+
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(16.0, 9.0));
+        camera.transform.set_position(Vector3D::new(0, 0, -2));
+        camera.set_view_size(12.0);
+        camera.set_projection_mode(ProjectionMode::Orthographic);
+
+        let scene = Scene { objects: vec![with_mesh, Object::new()], camera };
+        let scene_path = temp_path("scene.txt");
+        scene.save(&scene_path).unwrap();
+
+        let loaded = Scene::load(&scene_path).unwrap();
+        assert_eq!(loaded.objects.len(), 2);
+        assert_eq!(loaded.camera.field_of_view(), 90.0);
+        assert_eq!(loaded.camera.projection_mode(), ProjectionMode::Orthographic);
+        assert_eq!(loaded.camera.view_size(), 12.0);
+        assert_eq!(loaded.camera.transform.position(), Vector3D::new(0, 0, -2));
+
+        let first = &loaded.objects[0];
+        assert_eq!(first.transform.position(), Vector3D::new(1, 2, 3));
+        assert_eq!(first.transform.rotation(), scene.objects[0].transform.rotation());
+        assert_eq!(first.transform.scale(), Vector3D::new(1, 2, 1));
+        assert_eq!(first.get_component::<Mesh>().unwrap().triangles().len(), 1);
+        assert_eq!(first.get_component::<Material>(), scene.objects[0].get_component::<Material>());
+        assert!(loaded.objects[1].get_component::<Mesh>().is_none());
+        assert!(loaded.objects[1].get_component::<Material>().is_none());
+
+        fs::remove_file(obj_path).unwrap();
+        fs::remove_file(scene_path).unwrap();
+    }
+
+    fn triangle_object(obj_path: &str, position: Vector3D, scale: f64) -> Object {
+        let mut object = Object::new();
+        object.transform = Transform::from_trs(
+            position,
+            Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            Vector3D::new(scale, scale, scale)
+        );
+
+        let mut mesh = Mesh::new();
+        mesh.load_obj(obj_path).unwrap();
+        object.add_component(mesh);
+        object
+    }
+
+    #[test]
+    fn raycast_closest_object() {
+        let obj_path = temp_path("raycast.obj");
+        fs::write(&obj_path, "v -1 -1 0\nv 1 -1 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+
+        let mut scene = Scene {
+            objects: vec![
+                triangle_object(&obj_path, Vector3D::new(0, 0, 10), 1.0),
+                triangle_object(&obj_path, Vector3D::new(0, 0, 6), 2.0),
+                Object::new()
+            ],
+            camera: Camera::new(1, 30, 90, AspectRatio(16.0, 9.0))
+        };
+
+        let ray = Ray::new(Vector3D::new(0.75, 0.0, 0.0), Vector3D::new(0, 0, 1));
+        let hit = scene.raycast(&ray).unwrap();
+
+        // Only the scaled up triangle is wide enough at x = 0.75
+        assert_eq!(hit.object_index, 1);
+        assert_eq!(hit.triangle_index, 0);
+        assert_eq!(hit.distance, 6.0);
+        assert_eq!(hit.point, Vector3D::new(0.75, 0.0, 6.0));
+
+        let ray = Ray::new(Vector3D::new(0, 0, 0), Vector3D::new(0, 0, 1));
+        assert_eq!(scene.raycast(&ray).unwrap().object_index, 1);
+
+        scene.objects[1].transform.translate(Vector3D::new(0, 0, 10), Space::World);
+        assert_eq!(scene.raycast(&ray).unwrap().object_index, 0);
+
+        let ray = Ray::new(Vector3D::new(0, 5, 0), Vector3D::new(0, 0, 1));
+        assert_eq!(scene.raycast(&ray), None);
+
+        fs::remove_file(obj_path).unwrap();
+    }
+
+    #[test]
+    fn end_frame_clears_changed_flags() {
+        let mut scene = Scene {
+            objects: vec![Object::new(), Object::new()],
+            camera: Camera::new(1, 30, 90, AspectRatio(16.0, 9.0))
+        };
+
+        scene.camera.transform.rotate(0.0, 1.0, 0.0, Space::Local);
+        scene.objects[1].transform.set_scale(Vector3D::new(2, 2, 2));
+        assert!(scene.camera.transform.has_changed());
+        assert!(!scene.objects[0].transform.has_changed());
+        assert!(scene.objects[1].transform.has_changed());
+
+        scene.end_frame();
+        assert!(!scene.camera.transform.has_changed());
+        assert!(!scene.objects[1].transform.has_changed());
+    }
+
+    #[test]
+    fn load_scene_errors() {
+        let scene_path = temp_path("broken.txt");
+
+        fs::write(&scene_path, "object\nposition 1 2\n").unwrap();
+        let error = Scene::load(&scene_path).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Expected 3 numbers");
+
+        fs::write(&scene_path, "object\nmaterial glossy 1 2 3\n").unwrap();
+        let error = Scene::load(&scene_path).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Unknown blend mode 'glossy'");
+
+        fs::write(&scene_path, "object\nmaterial alpha 1 2 300\n").unwrap();
+        let error = Scene::load(&scene_path).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Expected 3 or 4 numbers from 0 to 255");
+
+        fs::write(&scene_path, "object\nmaterial alpha 1 2 3 4 5 6\n").unwrap();
+        let error = Scene::load(&scene_path).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: Expected a single intensity after the alpha");
+
+        fs::write(&scene_path, "object\n").unwrap();
+        let error = Scene::load(&scene_path).unwrap_err();
+        assert_eq!(error.to_string(), "No camera found.");
+
+        fs::remove_file(scene_path).unwrap();
+    }
+}
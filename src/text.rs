@@ -0,0 +1,187 @@
+use crate::renderer::{Renderer, Color};
+use crate::object::Camera;
+use crate::math_utils::vector3d::Vector3D;
+
+pub mod font;
+
+pub use font::{Font, Glyph};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom
+}
+
+// How text is laid out around the position it's drawn at
+// Each line is aligned on its own, the vertical alignment is for the whole block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub color: Color,
+    // Each font pixel becomes a scale x scale square
+    pub scale: usize,
+    pub align: Align,
+    pub vertical_align: VerticalAlign
+}
+
+impl TextStyle {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            scale: 1,
+            align: Align::Left,
+            vertical_align: VerticalAlign::Top
+        }
+    }
+
+    pub fn scale(self, scale: usize) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn align(self, align: Align, vertical_align: VerticalAlign) -> Self {
+        Self { align, vertical_align, ..self }
+    }
+}
+
+// Clipped to the framebuffer, '\n' starts a new line
+pub fn draw_text(renderer: &mut Renderer, font: &Font, x: isize, y: isize, text: &str, style: &TextStyle) {
+    let scale = style.scale as isize;
+    let line_height = font.line_height() as isize * scale;
+
+    let block_height = font.text_height(text) as isize * scale;
+    let top = match style.vertical_align {
+        VerticalAlign::Top => y,
+        VerticalAlign::Middle => y - block_height / 2,
+        VerticalAlign::Bottom => y - block_height
+    };
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_width = font.text_width(line) as isize * scale;
+        let mut pen_x = match style.align {
+            Align::Left => x,
+            Align::Center => x - line_width / 2,
+            Align::Right => x - line_width
+        };
+        let pen_y = top + line_index as isize * line_height;
+
+        for character in line.chars() {
+            let glyph = match font.glyph(character) {
+                Some(glyph) => glyph,
+                None => continue
+            };
+
+            let glyph_x = pen_x + glyph.x_offset * scale;
+            let glyph_y = pen_y + glyph.y_offset * scale;
+            for row in 0..glyph.height {
+                for column in 0..glyph.width {
+                    if !glyph.is_set(column, row) { continue }
+
+                    let px = glyph_x + column as isize * scale;
+                    let py = glyph_y + row as isize * scale;
+                    renderer.fill_rect(px, py, scale, scale, style.color);
+                }
+            }
+
+            pen_x += glyph.advance as isize * scale;
+        }
+    }
+}
+
+// Text anchored to a point in the world, always facing the screen at the same size
+// Nothing is drawn if the point is behind the camera
+pub fn draw_text_world(
+    renderer: &mut Renderer, camera: &mut Camera, font: &Font,
+    position: Vector3D, text: &str, style: &TextStyle) {
+
+    if let Some((x, y)) = renderer.project_to_pixel(camera, position) {
+        draw_text(renderer, font, x, y, text, style);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::AspectRatio;
+
+    fn lit_pixels(renderer: &Renderer) -> Vec<(usize, usize)> {
+        renderer.buffer().iter().enumerate()
+            .filter(|(_, &pixel)| pixel != 0)
+            .map(|(i, _)| (i % renderer.width(), i / renderer.width()))
+            .collect()
+    }
+
+    #[test]
+    fn draws_scaled_glyphs() {
+        let mut renderer = Renderer::new(16, 12);
+        let color = Color::new(255, 255, 255);
+        draw_text(&mut renderer, Font::builtin(), 0, 0, "1", &TextStyle::new(color).scale(2));
+
+        // '1' has 8 pixels lit, each one a 2x2 square
+        assert_eq!(lit_pixels(&renderer).len(), 8 * 4);
+        // The top row is 0b010
+        assert_eq!(renderer.buffer()[0], 0);
+        assert_eq!(renderer.buffer()[2], color.rgb_u32());
+    }
+
+    #[test]
+    fn text_off_screen_is_clipped() {
+        let mut renderer = Renderer::new(4, 4);
+        let style = TextStyle::new(Color::new(1, 1, 1));
+        draw_text(&mut renderer, Font::builtin(), -6, -2, "HELLO", &style);
+        draw_text(&mut renderer, Font::builtin(), 100, 100, "HELLO", &style);
+    }
+
+    #[test]
+    fn alignment() {
+        let font = Font::builtin();
+        let style = TextStyle::new(Color::new(1, 1, 1));
+
+        // "-" is lit on row 2, columns 0 to 2, and advances 4
+        let mut renderer = Renderer::new(20, 20);
+        draw_text(&mut renderer, font, 10, 10, "-", &style.align(Align::Right, VerticalAlign::Bottom));
+        assert_eq!(lit_pixels(&renderer), vec![(6, 6), (7, 6), (8, 6)]);
+
+        let mut renderer = Renderer::new(20, 20);
+        draw_text(&mut renderer, font, 10, 10, "-", &style.align(Align::Center, VerticalAlign::Middle));
+        assert_eq!(lit_pixels(&renderer), vec![(8, 9), (9, 9), (10, 9)]);
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let font = Font::builtin();
+        let mut renderer = Renderer::new(20, 20);
+        draw_text(&mut renderer, font, 0, 0, "-\n--", &TextStyle::new(Color::new(1, 1, 1)));
+
+        let lit = lit_pixels(&renderer);
+        assert_eq!(lit.len(), 9);
+        assert!(lit.contains(&(0, 2 + font.line_height())));
+        assert_eq!(font.text_width("-\n--"), 8);
+        assert_eq!(font.text_height("-\n--"), 2 * font.line_height());
+    }
+
+    #[test]
+    fn billboard_behind_the_camera() {
+        let mut renderer = Renderer::new(40, 40);
+        let mut camera = Camera::new(1, 30, 90, AspectRatio(1.0, 1.0));
+        let style = TextStyle::new(Color::new(1, 1, 1)).align(Align::Center, VerticalAlign::Middle);
+
+        draw_text_world(&mut renderer, &mut camera, Font::builtin(), Vector3D::new(0, 0, -5), "X", &style);
+        assert!(lit_pixels(&renderer).is_empty());
+
+        // Straight ahead lands in the middle of the screen
+        draw_text_world(&mut renderer, &mut camera, Font::builtin(), Vector3D::new(0, 0, 5), "X", &style);
+        let lit = lit_pixels(&renderer);
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|&(x, y)| (16..24).contains(&x) && (16..24).contains(&y)));
+    }
+}
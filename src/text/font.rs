@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::sync::OnceLock;
+
+// The built-in 3x5 font, upper case only
+// Every glyph advances 4 pixels, so there's a column of spacing after each one
+const BUILTIN_GLYPHS: [(char, [u8; 5]); 50] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('|', [0b010, 0b010, 0b010, 0b010, 0b010]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('*', [0b101, 0b010, 0b101, 0b000, 0b000]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+];
+// Question mark for anything the font can't draw
+const BUILTIN_FALLBACK: [u8; 5] = [0b111, 0b001, 0b011, 0b000, 0b010];
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+// Pixels of a single character, row by row from the top
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    pub width: usize,
+    pub height: usize,
+    // Where the bitmap's top left corner goes, relative to the pen at the top of the line
+    pub x_offset: isize,
+    pub y_offset: isize,
+    // How far the pen moves on afterwards
+    pub advance: usize,
+    bitmap: Vec<bool>
+}
+
+impl Glyph {
+    pub fn new(width: usize, height: usize, x_offset: isize, y_offset: isize, advance: usize, bitmap: Vec<bool>) -> Self {
+        assert_eq!(bitmap.len(), width * height, "Glyph bitmap doesn't match its size");
+        Self { width, height, x_offset, y_offset, advance, bitmap }
+    }
+
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.bitmap[x + y * self.width]
+    }
+
+    // Rows of bits, most significant bit first, eg. straight out of BDF and PSF files
+    fn from_rows(width: usize, height: usize, x_offset: isize, y_offset: isize, advance: usize, rows: &[&[u8]]) -> Self {
+        let bitmap = rows.iter()
+            .flat_map(|row| (0..width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0))
+            .collect();
+
+        Self::new(width, height, x_offset, y_offset, advance, bitmap)
+    }
+}
+
+// A bitmap font, either the built-in one or loaded from a BDF or PSF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    // Drawn for characters the font doesn't have
+    fallback: Option<Glyph>,
+    // Distance between the tops of two lines, in font pixels
+    line_height: usize
+}
+
+impl Font {
+    pub fn new(line_height: usize) -> Self {
+        Self {
+            glyphs: HashMap::new(),
+            fallback: None,
+            line_height
+        }
+    }
+
+    // Built once, then shared
+    pub fn builtin() -> &'static Font {
+        static BUILTIN: OnceLock<Font> = OnceLock::new();
+
+        BUILTIN.get_or_init(|| {
+            let glyph = |bits: &[u8; 5]| {
+                // Shifted to the top of the byte, like the bitmaps of font files
+                let rows = bits.map(|row| [row << 5]);
+                Glyph::from_rows(3, 5, 0, 0, 4, &rows.iter().map(|row| row.as_slice()).collect::<Vec<&[u8]>>())
+            };
+
+            let mut font = Font::new(6);
+            for (character, bits) in BUILTIN_GLYPHS.iter() {
+                font.insert(*character, glyph(bits));
+            }
+            font.fallback = Some(glyph(&BUILTIN_FALLBACK));
+            font
+        })
+    }
+
+    pub fn line_height(&self) -> usize {
+        self.line_height
+    }
+
+    pub fn insert(&mut self, character: char, glyph: Glyph) {
+        self.glyphs.insert(character, glyph);
+    }
+
+    // Falls back to upper case, for fonts that don't have lower case,
+    // then to the font's fallback glyph, or its question mark
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character)
+            .or_else(|| self.glyphs.get(&character.to_ascii_uppercase()))
+            .or(self.fallback.as_ref())
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    // Width in font pixels of the longest line
+    pub fn text_width(&self, text: &str) -> usize {
+        text.lines()
+            .map(|line| line.chars().filter_map(|character| self.glyph(character)).map(|glyph| glyph.advance).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Height in font pixels, every line counts as a full line_height
+    pub fn text_height(&self, text: &str) -> usize {
+        text.lines().count() * self.line_height
+    }
+
+    pub fn load_bdf(file_path: &str) -> io::Result<Self> {
+        Self::parse_bdf(&fs::read_to_string(file_path)?)
+    }
+
+    // Glyph Bitmap Distribution Format, only the parts needed to draw:
+    // FONTBOUNDINGBOX, FONT_ASCENT/FONT_DESCENT, and each char's ENCODING, DWIDTH, BBX and BITMAP
+    pub fn parse_bdf(text: &str) -> io::Result<Self> {
+        let mut bounding_box: Option<[isize; 4]> = None;
+        let mut ascent: Option<isize> = None;
+        let mut descent: Option<isize> = None;
+        // Glyphs are placed once the ascent is known, which can come after some of them
+        let mut glyphs: Vec<BdfChar> = vec![];
+
+        let mut current: Option<BdfChar> = None;
+        let mut bitmap_rows: Option<Vec<Vec<u8>>> = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| invalid_data(line_number + 1, message);
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let numbers = |count: usize| -> io::Result<Vec<isize>> {
+                let numbers = rest.split_whitespace()
+                    .map(|token| token.parse::<isize>())
+                    .collect::<Result<Vec<isize>, _>>()
+                    .map_err(|_| error(&format!("Expected {} numbers", count)))?;
+                if numbers.len() < count { return Err(error(&format!("Expected {} numbers", count))) }
+                Ok(numbers)
+            };
+
+            if let Some(rows) = bitmap_rows.as_mut() {
+                if keyword != "ENDCHAR" {
+                    let invalid_row = || error(&format!("Invalid bitmap row '{}'", line));
+                    // Rows are sliced by bytes, which only lines up with characters in ASCII
+                    if !line.is_ascii() { return Err(invalid_row()) }
+                    let row = (0..line.len() / 2)
+                        .map(|i| u8::from_str_radix(&line[i * 2..i * 2 + 2], 16))
+                        .collect::<Result<Vec<u8>, _>>()
+                        .map_err(|_| invalid_row())?;
+                    rows.push(row);
+                    continue;
+                }
+            }
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let n = numbers(4)?;
+                    bounding_box = Some([n[0], n[1], n[2], n[3]]);
+                },
+                "FONT_ASCENT" => ascent = Some(numbers(1)?[0]),
+                "FONT_DESCENT" => descent = Some(numbers(1)?[0]),
+                "STARTCHAR" => current = Some(BdfChar::default()),
+                "ENCODING" | "DWIDTH" | "BBX" | "BITMAP" => {
+                    let bdf_char = current.as_mut().ok_or_else(|| error(&format!("{} given outside of a char", keyword)))?;
+                    match keyword {
+                        // -1 means the glyph has no standard encoding, so it can't be typed anyway
+                        "ENCODING" => bdf_char.character = u32::try_from(numbers(1)?[0]).ok().and_then(char::from_u32),
+                        "DWIDTH" => bdf_char.advance = numbers(1)?[0].max(0) as usize,
+                        "BBX" => {
+                            let n = numbers(4)?;
+                            bdf_char.bbx = Some([n[0], n[1], n[2], n[3]]);
+                        },
+                        _ => bitmap_rows = Some(vec![])
+                    }
+                },
+                "ENDCHAR" => {
+                    let mut bdf_char = current.take().ok_or_else(|| error("ENDCHAR given outside of a char"))?;
+                    bdf_char.rows = bitmap_rows.take().unwrap_or_default();
+                    let [width, height, ..] = bdf_char.bbx.or(bounding_box).ok_or_else(|| error("Char has no BBX"))?;
+
+                    let row_bytes = (width.max(0) as usize).div_ceil(8);
+                    if bdf_char.rows.len() < height.max(0) as usize || bdf_char.rows.iter().any(|row| row.len() < row_bytes) {
+                        return Err(error("Bitmap is smaller than the BBX"));
+                    }
+                    if bdf_char.character.is_some() {
+                        glyphs.push(bdf_char);
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        let bounding_box = bounding_box.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No FONTBOUNDINGBOX found."))?;
+        let ascent = ascent.unwrap_or(bounding_box[1] + bounding_box[3]);
+        let descent = descent.unwrap_or(-bounding_box[3]);
+
+        let mut font = Font::new((ascent + descent).max(1) as usize);
+        for bdf_char in glyphs {
+            let [width, height, x_offset, y_offset] = bdf_char.bbx.unwrap_or(bounding_box);
+            let rows = bdf_char.rows.iter().map(|row| row.as_slice()).collect::<Vec<&[u8]>>();
+            let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+            // BBX offsets are from the baseline, up is positive
+            let top = ascent - (y_offset + height as isize);
+            font.insert(bdf_char.character.unwrap(), Glyph::from_rows(width, height, x_offset, top, bdf_char.advance, &rows[..height]));
+        }
+
+        Ok(font)
+    }
+
+    pub fn load_psf(file_path: &str) -> io::Result<Self> {
+        Self::parse_psf(&fs::read(file_path)?)
+    }
+
+    // PC Screen Font, version 1 or 2, as used by the Linux console
+    // Without a unicode table, glyph N is character N
+    pub fn parse_psf(bytes: &[u8]) -> io::Result<Self> {
+        let error = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let truncated = || error("Truncated PSF font");
+        let u32_at = |offset: usize| -> io::Result<usize> {
+            let word = bytes.get(offset..offset + 4).ok_or_else(truncated)?;
+            Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]) as usize)
+        };
+
+        let (glyph_count, header_size, glyph_size, width, height, has_table, version) = if bytes.starts_with(&PSF1_MAGIC) {
+            let mode = *bytes.get(2).ok_or_else(truncated)?;
+            let height = *bytes.get(3).ok_or_else(truncated)? as usize;
+            let glyph_count = if mode & 0x01 != 0 { 512 } else { 256 };
+            (glyph_count, 4, height, 8, height, mode & 0x06 != 0, 1)
+        } else if bytes.starts_with(&PSF2_MAGIC) {
+            let flags = u32_at(12)?;
+            (u32_at(16)?, u32_at(8)?, u32_at(20)?, u32_at(28)?, u32_at(24)?, flags & 0x01 != 0, 2)
+        } else {
+            return Err(error("Not a PSF font"));
+        };
+
+        // The header's sizes aren't trusted, a broken one shouldn't overflow
+        let row_bytes = width.div_ceil(8);
+        let min_glyph_size = row_bytes.checked_mul(height).ok_or_else(|| error("Invalid PSF glyph size"))?;
+        if width == 0 || height == 0 || glyph_size < min_glyph_size {
+            return Err(error("Invalid PSF glyph size"));
+        }
+
+        let glyph_data = glyph_count.checked_mul(glyph_size)
+            .and_then(|length| header_size.checked_add(length))
+            .and_then(|end| bytes.get(header_size..end))
+            .ok_or_else(truncated)?;
+
+        let glyphs = glyph_data.chunks(glyph_size).map(|data| {
+            let rows = data.chunks(row_bytes).take(height).collect::<Vec<&[u8]>>();
+            Glyph::from_rows(width, height, 0, 0, width, &rows)
+        }).collect::<Vec<Glyph>>();
+
+        let characters = if has_table {
+            let table = &bytes[header_size + glyph_data.len()..];
+            if version == 1 { psf1_unicode_table(table, glyph_count) } else { psf2_unicode_table(table, glyph_count) }
+        } else {
+            (0..glyph_count).map(|index| char::from_u32(index as u32).into_iter().collect()).collect()
+        };
+
+        let mut font = Font::new(height);
+        for (glyph, characters) in glyphs.iter().zip(characters) {
+            for character in characters {
+                font.insert(character, glyph.clone());
+            }
+        }
+
+        Ok(font)
+    }
+}
+
+// A BDF char while it's being read
+#[derive(Default)]
+struct BdfChar {
+    character: Option<char>,
+    advance: usize,
+    // Width, height and offsets, the font's bounding box if missing
+    bbx: Option<[isize; 4]>,
+    rows: Vec<Vec<u8>>
+}
+
+// Per glyph, 16 bit characters ending with 0xFFFF
+// 0xFFFE starts the combining sequences, which can't be drawn on their own
+fn psf1_unicode_table(table: &[u8], glyph_count: usize) -> Vec<Vec<char>> {
+    let mut entries = table.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+
+    (0..glyph_count).map(|_| {
+        let mut characters = vec![];
+        let mut in_sequence = false;
+        for value in entries.by_ref() {
+            match value {
+                0xFFFF => break,
+                0xFFFE => in_sequence = true,
+                _ if !in_sequence => characters.extend(char::from_u32(value as u32)),
+                _ => {}
+            }
+        }
+        characters
+    }).collect()
+}
+
+// Per glyph, UTF-8 characters ending with 0xFF, 0xFE starts the combining sequences
+fn psf2_unicode_table(table: &[u8], glyph_count: usize) -> Vec<Vec<char>> {
+    let mut entries = table.split(|&byte| byte == 0xFF);
+
+    (0..glyph_count).map(|_| {
+        let entry = entries.next().unwrap_or_default();
+        let singles = entry.split(|&byte| byte == 0xFE).next().unwrap_or_default();
+        String::from_utf8_lossy(singles).chars().filter(|&character| character != char::REPLACEMENT_CHARACTER).collect()
+    }).collect()
+}
+
+fn invalid_data(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {}", line_number, message))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x4 font with an 'A' (box outline) and a 'b' that hangs below the baseline
+    const BDF: &str = "STARTFONT 2.1
+FONT test
+SIZE 4 75 75
+FONTBOUNDINGBOX 4 6 0 -2
+STARTPROPERTIES 2
+FONT_ASCENT 4
+FONT_DESCENT 2
+ENDPROPERTIES
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 5 0
+BBX 4 4 0 0
+BITMAP
+F0
+90
+90
+F0
+ENDCHAR
+STARTCHAR b
+ENCODING 98
+DWIDTH 3 0
+BBX 2 3 1 -2
+BITMAP
+80
+C0
+40
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 3 0
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    fn bits(glyph: &Glyph) -> Vec<Vec<bool>> {
+        (0..glyph.height).map(|y| (0..glyph.width).map(|x| glyph.is_set(x, y)).collect()).collect()
+    }
+
+    #[test]
+    fn parse_bdf_font() {
+        let font = Font::parse_bdf(BDF).unwrap();
+        assert_eq!(font.line_height(), 6);
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.x_offset, a.y_offset, a.advance), (4, 4, 0, 0, 5));
+        assert_eq!(bits(a)[1], vec![true, false, false, true]);
+
+        // Sits 2 pixels below the baseline, which is 4 down from the top
+        let b = font.glyph('b').unwrap();
+        assert_eq!((b.x_offset, b.y_offset), (1, 3));
+        assert_eq!(bits(b), vec![vec![true, false], vec![true, true], vec![false, true]]);
+
+        // Lower case falls back to upper case, and there's no question mark to fall back to
+        assert_eq!(font.glyph('a'), font.glyph('A'));
+        assert_eq!(font.glyph('z'), None);
+        assert_eq!(font.text_width("Ab"), 8);
+    }
+
+    #[test]
+    fn bdf_errors() {
+        let error = Font::parse_bdf("STARTFONT 2.1\nENDFONT\n").unwrap_err();
+        assert_eq!(error.to_string(), "No FONTBOUNDINGBOX found.");
+
+        let error = Font::parse_bdf(&BDF.replace("BBX 4 4 0 0", "BBX 4 x 0 0")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 13: Expected 4 numbers");
+
+        let error = Font::parse_bdf(&BDF.replace("F0\nENDCHAR", "ENDCHAR")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 18: Bitmap is smaller than the BBX");
+
+        let error = Font::parse_bdf(&BDF.replace("F0\nENDCHAR", "F\u{e9}\nENDCHAR")).unwrap_err();
+        assert_eq!(error.to_string(), "Line 18: Invalid bitmap row 'F\u{e9}'");
+    }
+
+    fn psf2(glyphs: &[[u8; 2]], unicode_table: Option<&[u8]>) -> Vec<u8> {
+        let mut bytes = PSF2_MAGIC.to_vec();
+        let header = [0, 32, unicode_table.is_some() as u32, glyphs.len() as u32, 2, 2, 3];
+        bytes.extend(header.iter().flat_map(|word| word.to_le_bytes()));
+
+        bytes.extend(glyphs.iter().flatten());
+        bytes.extend(unicode_table.unwrap_or_default());
+        bytes
+    }
+
+    #[test]
+    fn parse_psf2_font() {
+        // 3 wide, 2 tall
+        let glyphs = [[0b1010_0000, 0b0100_0000], [0b1110_0000, 0b0000_0000]];
+
+        let font = Font::parse_psf(&psf2(&glyphs, None)).unwrap();
+        assert_eq!(font.line_height(), 2);
+        let first = font.glyph('\0').unwrap();
+        assert_eq!(bits(first), vec![vec![true, false, true], vec![false, true, false]]);
+        assert_eq!(first.advance, 3);
+
+        // Glyph 0 is 'x' and 'X', glyph 1 is '-' plus a combining sequence that's skipped
+        let table = b"xX\xFF-\xFEa\xCC\x81\xFF";
+        let font = Font::parse_psf(&psf2(&glyphs, Some(table))).unwrap();
+        assert_eq!(font.glyph('X'), Some(first));
+        assert_eq!(font.glyph('x'), Some(first));
+        assert_eq!(bits(font.glyph('-').unwrap())[0], vec![true, true, true]);
+        assert_eq!(font.glyph('a'), None);
+    }
+
+    #[test]
+    fn parse_psf1_font() {
+        let mut bytes = vec![PSF1_MAGIC[0], PSF1_MAGIC[1], 0x02, 1];
+        bytes.extend((0..256).map(|i| i as u8));
+        // Glyph 0 is 'Q', the rest have no characters
+        bytes.extend([b'Q', 0, 0xFF, 0xFF]);
+        bytes.extend([0xFF, 0xFF].repeat(255));
+
+        let font = Font::parse_psf(&bytes).unwrap();
+        assert_eq!(font.line_height(), 1);
+        assert_eq!(font.glyph('Q').unwrap().width, 8);
+        assert!(!font.glyph('Q').unwrap().is_set(0, 0));
+        assert_eq!(font.glyph('\u{1}'), None);
+    }
+
+    #[test]
+    fn psf_errors() {
+        assert_eq!(Font::parse_psf(b"nope").unwrap_err().to_string(), "Not a PSF font");
+
+        let mut bytes = psf2(&[[0, 0]], None);
+        bytes.pop();
+        assert_eq!(Font::parse_psf(&bytes).unwrap_err().to_string(), "Truncated PSF font");
+
+        // Glyph count and size big enough to overflow on 32 bit targets
+        let mut bytes = psf2(&[[0, 0]], None);
+        bytes[16..24].copy_from_slice(&[0xFF; 8]);
+        assert_eq!(Font::parse_psf(&bytes).unwrap_err().to_string(), "Truncated PSF font");
+
+        let mut bytes = psf2(&[[0, 0]], None);
+        bytes[24..28].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(Font::parse_psf(&bytes).unwrap_err().to_string(), "Invalid PSF glyph size");
+    }
+
+    #[test]
+    fn builtin_font() {
+        let font = Font::builtin();
+        assert_eq!(font.line_height(), 6);
+        assert_eq!(font.text_width("abc"), 12);
+        assert_eq!(font.text_width(""), 0);
+        // Unknown characters get the question mark
+        assert_eq!(font.glyph('~').unwrap().advance, 4);
+        assert!(font.glyph('~').unwrap().is_set(1, 4));
+    }
+}
@@ -1,81 +1,239 @@
+use std::cell::Cell;
+
 use crate::math_utils::{vector3d, quaternion, matrix4x4};
-use matrix4x4::{Matrix4x4, IDENTITY_MATRIX4X4, invert_matrix, vector_matrix_multiply};
+use matrix4x4::{Matrix4x4, MatrixError};
 use quaternion::{Quaternion, IDENTITY_QUATERNION};
 use vector3d::Vector3D;
 
 
-// The struct will have getter and setter
-// So that we can use a dirty flag to track any changes
-#[derive(Debug, PartialEq)]
+// Which axes relative changes are measured along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+    World,
+    // The object's own axes, following its rotation
+    Local
+}
+
+// Position, rotation and scale are the source of truth
+// The matrix and its inverse are caches, rebuilt on first use after a change,
+// so they're never stale even through a shared reference
+#[derive(Debug, Clone)]
 pub struct Transform {
-    matrix: Matrix4x4,
-    inverse_matrix: Matrix4x4,
+    position: Vector3D,
     rotation: Quaternion,
     scale: Vector3D,
-    dirty_flag: bool
+    matrix: Cell<Option<Matrix4x4>>,
+    inverse_matrix: Cell<Option<Matrix4x4>>,
+    // Set by any change, and only cleared by clear_changed(),
+    // which the engine does once per frame through Scene::end_frame()
+    changed: bool
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The caches are left out, they're the same thing for equal TRS
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+        && self.rotation == other.rotation
+        && self.scale == other.scale
+        && self.matrix() == other.matrix()
+    }
 }
 
 impl Transform {
     pub fn new() -> Self {
-        Self {
-            matrix: IDENTITY_MATRIX4X4,
-            inverse_matrix: IDENTITY_MATRIX4X4,
-            rotation: IDENTITY_QUATERNION,
-            scale: Vector3D::new(1,1,1),
-            dirty_flag: false
-        }
+        Self::from_trs(Vector3D::new(0, 0, 0), IDENTITY_QUATERNION, Vector3D::new(1,1,1))
     }
 
-    pub fn from_matrix(matrix: Matrix4x4) -> Result<Self, String>{
+    // Decomposed into position, rotation and scale
+    // Anything TRS can't express (shear, projection) is kept in the matrix
+    // until the first change, which rebuilds it from TRS
+    pub fn from_matrix(matrix: Matrix4x4) -> Result<Self, MatrixError> {
+        let inverse_matrix = matrix.inverse()?;
+
+        let mut axes = [0, 1, 2].map(|row| Vector3D::new(matrix[row][0], matrix[row][1], matrix[row][2]));
+        let mut scale = Vector3D::new(axes[0].length(), axes[1].length(), axes[2].length());
+
+        // A mirrored matrix can't be a rotation, so flip one axis back and keep it in the scale
+        if axes[0].cross(axes[1]).dot(axes[2]) < 0.0 {
+            scale.x = -scale.x;
+            axes[0] = -axes[0];
+        }
+
+        let [x, y, z] = axes.map(|axis| axis.normalize());
+        let rotation = Quaternion::from_rotation_matrix(&Matrix4x4::new([
+            [x.x, x.y, x.z, 0.0],
+            [y.x, y.y, y.z, 0.0],
+            [z.x, z.y, z.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]));
+
         Ok(
         Self {
-            matrix,
-            inverse_matrix: invert_matrix(&matrix, true)?,
-            rotation: IDENTITY_QUATERNION,
-            scale: Vector3D::new(1,1,1),
-            dirty_flag: false
+            position: Vector3D::new(matrix[3][0], matrix[3][1], matrix[3][2]),
+            rotation,
+            scale,
+            matrix: Cell::new(Some(matrix)),
+            inverse_matrix: Cell::new(Some(inverse_matrix)),
+            changed: false
         })
     }
 
+    pub fn from_trs(position: Vector3D, rotation: Quaternion, scale: Vector3D) -> Self {
+        Self {
+            position,
+            rotation,
+            scale,
+            matrix: Cell::new(None),
+            inverse_matrix: Cell::new(None),
+            changed: false
+        }
+    }
+
     pub fn matrix(&self) -> Matrix4x4 {
-       self.matrix 
+        if let Some(matrix) = self.matrix.get() { return matrix }
+
+        let matrix = Matrix4x4::from_trs(self.position, self.rotation, self.scale);
+        self.matrix.set(Some(matrix));
+        matrix
     }
 
-    pub fn inverse_matrix(&mut self) -> Matrix4x4 {
-        if self.dirty_flag {
-            self.inverse_matrix = invert_matrix(&self.matrix, true).unwrap();
-        }
+    pub fn inverse_matrix(&self) -> Matrix4x4 {
+        if let Some(inverse_matrix) = self.inverse_matrix.get() { return inverse_matrix }
 
-        self.inverse_matrix
+        let inverse_matrix = Matrix4x4::inverse_trs(self.position, self.rotation, self.scale);
+        self.inverse_matrix.set(Some(inverse_matrix));
+        inverse_matrix
     }
 
     #[inline]
     pub fn to_world_space(&self, coord: Vector3D) -> Vector3D {
-        vector_matrix_multiply(&self.matrix, coord, true)
+        self.matrix().transform_point(coord)
     }
 
     #[inline]
     pub fn to_local_space(&self, coord: Vector3D) -> Vector3D {
-        vector_matrix_multiply(&self.inverse_matrix, coord, true)
+        self.inverse_matrix().transform_point(coord)
     }
 
+    // Whether anything changed since the last clear_changed()
     pub fn has_changed(&self) -> bool {
-        self.dirty_flag
+        self.changed
     }
 
-    pub fn translate(&mut self, amount: Vector3D) -> () {
-        self.dirty_flag = true;
-        self.matrix[3][0] += amount.x;
-        self.matrix[3][1] += amount.y;
-        self.matrix[3][2] += amount.z;
+    pub fn clear_changed(&mut self) {
+        self.changed = false;
     }
 
-    pub fn rotate<T: Into<f64>>(&mut self, x: T, y: T, z: T) {
-        self.dirty_flag = true;
-        let new_q = Quaternion::from_euler_angles(x,y,z);
-        self.rotation *= new_q;
-        self.rotation.lazy_normalize();
-        self.rotation.update_3x4_matrix(&mut self.matrix, &self.scale);
+    pub fn position(&self) -> Vector3D {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Vector3D) {
+        self.position = position;
+        self.mark_changed();
+    }
+
+    pub fn translate(&mut self, amount: Vector3D, space: Space) {
+        let amount = match space {
+            Space::World => amount,
+            // Along the rotated axes, scale doesn't stretch the distance
+            Space::Local => self.rotation.rotate_vector(amount)
+        };
+
+        self.set_position(self.position + amount);
+    }
+
+    pub fn rotation(&self) -> Quaternion {
+        self.rotation
+    }
+
+    // Expected to be normalized, same as from_trs()
+    pub fn set_rotation(&mut self, rotation: Quaternion) {
+        self.rotation = rotation;
+        self.mark_changed();
+    }
+
+    // Euler angles in radians, applied like Quaternion::from_euler_angles()
+    pub fn rotate<T: Into<f64>>(&mut self, x: T, y: T, z: T, space: Space) {
+        self.rotate_by(Quaternion::from_euler_angles(x, y, z), space);
+    }
+
+    // Rotation always happens around the object's position
+    pub fn rotate_by(&mut self, rotation: Quaternion, space: Space) {
+        // Quaternions apply left to right, so the new rotation goes
+        // after the current one for world axes, and before it for local ones
+        let rotation = match space {
+            Space::World => self.rotation * rotation,
+            Space::Local => rotation * self.rotation
+        };
+
+        // Keeps rounding errors from piling up over many small rotations
+        self.set_rotation(rotation.normalize());
+    }
+
+    // Turns the local +Z towards target, keeping local +Y as close to up as possible
+    // Nothing happens if target is right on top of the position
+    pub fn look_at(&mut self, target: Vector3D, up: Vector3D) {
+        let forward = target - self.position;
+        if forward.length_squared() == 0.0 { return }
+
+        self.set_rotation(Quaternion::look_rotation(forward, up));
+    }
+
+    pub fn scale(&self) -> Vector3D {
+        self.scale
+    }
+
+    // Scale is along the object's local axes
+    pub fn set_scale(&mut self, scale: Vector3D) {
+        self.scale = scale;
+        self.mark_changed();
+    }
+
+    // Component-wise multiply
+    pub fn scale_by(&mut self, factor: Vector3D) {
+        let scale = Vector3D::new(
+            self.scale.x * factor.x,
+            self.scale.y * factor.y,
+            self.scale.z * factor.z
+        );
+
+        self.set_scale(scale);
+    }
+
+    // Directions the object's axes point to in world space, always normalized
+
+    pub fn local_forward(&self) -> Vector3D {
+        self.rotation.rotate_vector(Vector3D::new(0, 0, 1))
+    }
+
+    pub fn local_right(&self) -> Vector3D {
+        self.rotation.rotate_vector(Vector3D::new(1, 0, 0))
+    }
+
+    pub fn local_up(&self) -> Vector3D {
+        self.rotation.rotate_vector(Vector3D::new(0, 1, 0))
+    }
+
+    // Blends from self (t = 0) to other (t = 1), eg. to draw between two fixed updates
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Transform {
+        Self::from_trs(
+            self.position.lerp(other.position, t),
+            self.rotation.slerp(other.rotation, t),
+            self.scale.lerp(other.scale, t)
+        )
+    }
+
+    fn mark_changed(&mut self) {
+        self.changed = true;
+        self.matrix.set(None);
+        self.inverse_matrix.set(None);
     }
 }
 
@@ -85,6 +243,7 @@ mod tests {
     use super::*;
     use crate::test_utils::round_place;
     use rand::Rng;
+    use std::f64::consts::PI;
 
     fn round_vector3d(vector: Vector3D) -> [f64; 3] {
         [
@@ -97,12 +256,12 @@ mod tests {
     #[test]
     fn transform_to_world() {
         let mat_a = Vector3D::new(5.0,3.0,12.0);
-        let matrix = [
+        let matrix = Matrix4x4::new([
                 [0.9, 0.4, 0.12, 0.0],
                 [0.7, 0.06, 0.5, 0.0],
                 [0.1, 0.4, 2.7, 0.0],
                 [10.0,12.0,11.0, 1.0],
-            ];
+            ]);
         let transform_b = Transform::from_matrix(matrix).unwrap();
         let result = transform_b.to_world_space(mat_a);
         let rounded_result = round_vector3d(result);
@@ -113,12 +272,12 @@ mod tests {
     #[test]
     fn transform_to_local() {
         let mat_a = Vector3D::new(5,10,2);
-        let matrix = [
+        let matrix = Matrix4x4::new([
             [1.0, 0.0, 0.0, 0.0],
             [0.0, 1.0, 0.0, 0.0],
             [0.0, 0.0, 1.0, 0.0],
             [3.0, 4.0, 2.0, 1.0]
-        ];
+        ]);
         let transform_b = Transform::from_matrix(matrix).unwrap();
         let result = transform_b.to_local_space(mat_a);
         let rounded_result = round_vector3d(result);
@@ -136,17 +295,107 @@ mod tests {
         let vec_random = Vector3D::new(a,b,c);
 
         let mut transform = Transform::new();
-        transform.translate(vec_random);
-        assert_eq!(transform.has_changed(), true);
+        transform.translate(vec_random, Space::World);
+        assert!(transform.has_changed());
 
         let new_matrix = transform.matrix();
         assert_eq!(new_matrix[3], [a,b,c,1.0]);
     }
 
+    #[test]
+    fn transform_from_trs() {
+        let rotation = Quaternion::from_euler_angles(0.3, 0.2, 0.1);
+        let transform = Transform::from_trs(
+            Vector3D::new(1, 2, 3), rotation, Vector3D::new(2, 2, 2)
+        );
+
+        assert_eq!(transform.position(), Vector3D::new(1, 2, 3));
+        assert_eq!(transform.rotation(), rotation);
+        assert_eq!(transform.scale(), Vector3D::new(2, 2, 2));
+
+        let origin = transform.to_local_space(Vector3D::new(1, 2, 3));
+        assert_eq!(round_vector3d(origin), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn trs_inverse_matches_general_inverse() {
+        let mut transform = Transform::new();
+        transform.rotate(0.4, -1.2, 2.0, Space::World);
+        transform.set_scale(Vector3D::new(0.5, 3.0, 2.0));
+        transform.translate(Vector3D::new(-4, 8, 15), Space::World);
+
+        let general = transform.matrix().inverse().unwrap();
+        let fast = transform.inverse_matrix();
+        for (fast_row, general_row) in fast.rows().iter().zip(general.rows()) {
+            for (a, b) in fast_row.iter().zip(general_row) {
+                assert_eq!(round_place(*a, 9), round_place(b, 9));
+            }
+        }
+
+        let point = Vector3D::new(1, 2, 3);
+        let round_trip = transform.inverse_matrix().transform_point(transform.to_world_space(point));
+        assert_eq!(round_vector3d(round_trip), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn translate_then_to_local_space() {
+        let mut transform = Transform::new();
+        let point = Vector3D::new(5, 10, 2);
+        assert_eq!(round_vector3d(transform.to_local_space(point)), [5.0, 10.0, 2.0]);
+
+        transform.translate(Vector3D::new(3, 4, 2), Space::World);
+        assert_eq!(round_vector3d(transform.to_local_space(point)), [2.0, 6.0, 0.0]);
+
+        transform.translate(Vector3D::new(1, 1, 1), Space::World);
+        assert_eq!(round_vector3d(transform.to_local_space(point)), [1.0, 5.0, -1.0]);
+    }
+
+    #[test]
+    fn read_only_access_is_never_stale() {
+        let mut transform = Transform::new();
+        // Fill the caches
+        transform.matrix();
+        transform.inverse_matrix();
+
+        transform.set_scale(Vector3D::new(2, 2, 2));
+        let transform = &transform;
+        assert_eq!(transform.to_world_space(Vector3D::new(1, 0, 0)), Vector3D::new(2, 0, 0));
+        assert_eq!(transform.to_local_space(Vector3D::new(2, 0, 0)), Vector3D::new(1, 0, 0));
+    }
+
+    #[test]
+    fn changed_flag_lasts_until_cleared() {
+        let mut transform = Transform::new();
+        assert!(!transform.has_changed());
+
+        transform.rotate(0.0, 1.0, 0.0, Space::Local);
+        transform.inverse_matrix();
+        assert!(transform.has_changed());
+
+        transform.clear_changed();
+        assert!(!transform.has_changed());
+    }
+
+    #[test]
+    fn from_matrix_decomposes_trs() {
+        let rotation = Quaternion::from_euler_angles(0.3, -1.1, 2.4);
+        let original = Transform::from_trs(Vector3D::new(1, 2, 3), rotation, Vector3D::new(2.0, 0.5, 3.0));
+        let decomposed = Transform::from_matrix(original.matrix()).unwrap();
+
+        assert_eq!(decomposed.position(), Vector3D::new(1, 2, 3));
+        assert_eq!(round_vector3d(decomposed.scale()), [2.0, 0.5, 3.0]);
+        assert_eq!(round_place(decomposed.rotation().dot(rotation).abs(), 9), 1.0);
+
+        // Mirrored, the flip ends up in the scale
+        let mirrored = Transform::from_matrix(Matrix4x4::scale(Vector3D::new(-1, 1, 1))).unwrap();
+        assert_eq!(mirrored.scale(), Vector3D::new(-1, 1, 1));
+        assert_eq!(mirrored.rotation(), IDENTITY_QUATERNION);
+    }
+
     #[test]
     fn rotate_transform() {
         let mut transform = Transform::new();
-        transform.rotate(1.0, 2.0, 0.5);
+        transform.rotate(1.0, 2.0, 0.5, Space::World);
         let result = transform.matrix();
 
         let expected = [
@@ -156,10 +405,138 @@ mod tests {
             [0.0, 0.0, 0.0, 1.0],
         ];
 
-        for (i,row) in result.iter().enumerate() {
+        for (i,row) in result.rows().iter().enumerate() {
             for (j,num) in row.iter().enumerate() {
                 assert_eq!(round_place(*num, 5), expected[i][j]);
             }
         }
     }
+
+    #[test]
+    fn set_position_rotation_scale() {
+        let mut transform = Transform::new();
+        let rotation = Quaternion::from_euler_angles(0.0, 1.0, 0.0);
+
+        transform.set_position(Vector3D::new(1, 2, 3));
+        transform.set_rotation(rotation);
+        transform.set_scale(Vector3D::new(2, 3, 4));
+
+        assert_eq!(transform.position(), Vector3D::new(1, 2, 3));
+        assert_eq!(transform.rotation(), rotation);
+        assert_eq!(transform.scale(), Vector3D::new(2, 3, 4));
+        assert_eq!(transform.matrix(), Transform::from_trs(
+            Vector3D::new(1, 2, 3), rotation, Vector3D::new(2, 3, 4)
+        ).matrix());
+
+        transform.scale_by(Vector3D::new(0.5, 2.0, 1.0));
+        assert_eq!(transform.scale(), Vector3D::new(1, 6, 4));
+    }
+
+    #[test]
+    fn non_uniform_scale_with_rotation() {
+        // Stretched along X, then turned a quarter around Y
+        let transform = Transform::from_trs(
+            Vector3D::new(0, 0, 0),
+            Quaternion::from_euler_angles(0.0, std::f64::consts::FRAC_PI_2, 0.0),
+            Vector3D::new(3, 1, 1)
+        );
+
+        let tip = transform.to_world_space(Vector3D::new(1, 0, 0));
+        assert_eq!(round_vector3d(tip), round_vector3d(transform.local_right() * 3.0));
+        assert_eq!(round_place(tip.length(), 5), 3.0);
+
+        let side = transform.to_world_space(Vector3D::new(0, 0, 1));
+        assert_eq!(round_place(side.length(), 5), 1.0);
+    }
+
+    #[test]
+    fn translate_local_space() {
+        let mut transform = Transform::new();
+        transform.rotate(0.0, std::f64::consts::FRAC_PI_2, 0.0, Space::World);
+        transform.set_scale(Vector3D::new(5, 5, 5));
+
+        transform.translate(Vector3D::new(0, 0, 2), Space::Local);
+        assert_eq!(round_vector3d(transform.position()), round_vector3d(transform.local_forward() * 2.0));
+
+        transform.translate(Vector3D::new(0, 0, 2), Space::World);
+        let expected = transform.local_forward() * 2.0 + Vector3D::new(0, 0, 2);
+        assert_eq!(round_vector3d(transform.position()), round_vector3d(expected));
+    }
+
+    #[test]
+    fn rotate_world_and_local_space() {
+        let tilt = Quaternion::from_euler_angles(1.0, 0.0, 0.0);
+        let turn = Quaternion::from_euler_angles(0.0, 1.0, 0.0);
+
+        let mut world = Transform::from_trs(Vector3D::new(0, 0, 0), tilt, Vector3D::new(1, 1, 1));
+        world.rotate_by(turn, Space::World);
+        let mut local = Transform::from_trs(Vector3D::new(0, 0, 0), tilt, Vector3D::new(1, 1, 1));
+        local.rotate_by(turn, Space::Local);
+
+        // World: turn around the world's Y, so the world up stays put
+        let world_up = Vector3D::new(0, 1, 0);
+        assert_eq!(
+            round_vector3d(world.local_up()),
+            round_vector3d(turn.rotate_vector(tilt.rotate_vector(world_up)))
+        );
+        // Local: turn around the object's own (tilted) up, which stays put instead
+        assert_eq!(round_vector3d(local.local_up()), round_vector3d(tilt.rotate_vector(world_up)));
+        assert_ne!(round_vector3d(world.local_forward()), round_vector3d(local.local_forward()));
+    }
+
+    #[test]
+    fn look_at_target() {
+        let mut transform = Transform::new();
+        transform.set_position(Vector3D::new(0, 5, -5));
+        transform.look_at(Vector3D::new(0, 0, 0), Vector3D::new(0, 1, 0));
+
+        let forward = Vector3D::new(0, -1, 1).normalize();
+        assert_eq!(round_vector3d(transform.local_forward()), round_vector3d(forward));
+        assert_eq!(round_vector3d(transform.local_right()), [1.0, 0.0, 0.0]);
+
+        // Same as the view matrix built straight from the eye and target
+        let view = Matrix4x4::look_at(Vector3D::new(0, 5, -5), Vector3D::new(0, 0, 0), Vector3D::new(0, 1, 0));
+        let point = Vector3D::new(1, 2, 3);
+        assert_eq!(round_vector3d(transform.to_local_space(point)), round_vector3d(view.transform_point(point)));
+
+        let rotation = transform.rotation();
+        transform.look_at(transform.position(), Vector3D::new(0, 1, 0));
+        assert_eq!(transform.rotation(), rotation);
+    }
+
+    #[test]
+    fn local_directions() {
+        let transform = Transform::from_trs(
+            Vector3D::new(4, 4, 4),
+            Quaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_2),
+            Vector3D::new(2, 2, 2)
+        );
+
+        // Clockwise a quarter turn around Z, looking down Z
+        assert_eq!(round_vector3d(transform.local_right()), [0.0, -1.0, 0.0]);
+        assert_eq!(round_vector3d(transform.local_up()), [1.0, 0.0, 0.0]);
+        assert_eq!(round_vector3d(transform.local_forward()), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn interpolate_halfway() {
+        let from = Transform::new();
+        let to = Transform::from_trs(
+            Vector3D::new(2.0, 0.0, -4.0),
+            Quaternion::from_euler_angles(0.0, PI / 2.0, 0.0),
+            Vector3D::new(3.0, 1.0, 1.0)
+        );
+
+        let halfway = from.interpolate(&to, 0.5);
+        assert_eq!(halfway.position(), Vector3D::new(1.0, 0.0, -2.0));
+        assert_eq!(halfway.scale(), Vector3D::new(2.0, 1.0, 1.0));
+
+        let expected = Quaternion::from_euler_angles(0.0, PI / 4.0, 0.0).components();
+        let rotation = halfway.rotation().components();
+        for i in 0..4 {
+            assert_eq!(round_place(rotation[i], 6), round_place(expected[i], 6));
+        }
+
+        assert_eq!(from.interpolate(&to, 1.0).position(), to.position());
+    }
 }